@@ -37,5 +37,53 @@ pub enum ErrorCode {
     #[msg("Creator mismatch")]
     CreatorMismatch,
     #[msg("Overflow")]
-    Overflow
+    Overflow,
+    #[msg("Soft cap reached, refunds are not available")]
+    SoftCapReached,
+    #[msg("Soft cap not reached, proceeds cannot be withdrawn")]
+    SoftCapNotReached,
+    #[msg("Protocol owner mismatch")]
+    ProtocolOwnerMismatch,
+    #[msg("Fee cannot exceed 10000 basis points")]
+    InvalidFee,
+    #[msg("Vault cannot be closed while refunds are possible or tokens are still owed")]
+    VaultCloseNotAllowed,
+    #[msg("Invalid vesting schedule")]
+    InvalidVestingSchedule,
+    #[msg("Vesting is not enabled for this ICO")]
+    VestingNotEnabled,
+    #[msg("No vested tokens available to claim")]
+    NothingToClaim,
+    #[msg("Minimum purchase cannot exceed the per-wallet maximum")]
+    InvalidPurchaseLimits,
+    #[msg("Purchase amount is below the minimum")]
+    BelowMinPurchase,
+    #[msg("Purchase exceeds the per-wallet maximum")]
+    ExceedsMaxPurchasePerWallet,
+    #[msg("Allowlist phase must end within the sale window")]
+    InvalidAllowlistPhase,
+    #[msg("Buyer is not on the allowlist")]
+    NotAllowlisted,
+    #[msg("Purchase exceeds the allowlist allocation")]
+    ExceedsAllocation,
+    #[msg("Invalid sale tiers")]
+    InvalidSaleTiers,
+    #[msg("Sale tiers account is required for this ICO")]
+    MissingSaleTiers,
+    #[msg("Not enough tokens left in the active sale tiers")]
+    SaleTiersSoldOut,
+    #[msg("Payment mint does not match the ICO")]
+    PaymentMintMismatch,
+    #[msg("Payment mint, token accounts and token program are required for this ICO")]
+    MissingPaymentAccounts,
+    #[msg("Not enough payment tokens to buy tokens")]
+    NotEnoughPaymentTokens,
+    #[msg("ICO config account is not in the legacy layout")]
+    NotLegacyICOConfig,
+    #[msg("Extra accounts do not match the mint's transfer hook ExtraAccountMetaList")]
+    InvalidTransferHookAccounts,
+    #[msg("Signer is not the pending owner")]
+    NotPendingOwner,
+    #[msg("Protocol config account is not in the legacy layout")]
+    NotLegacyConfig
 }
@@ -0,0 +1,27 @@
+use anchor_lang::prelude::*;
+
+use crate::states::Config;
+use crate::errors::ErrorCode;
+
+
+#[derive(Accounts)]
+pub struct AcceptOwner<'info>{
+    pub new_owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.pending_owner == Some(new_owner.key()) @ ErrorCode::NotPendingOwner
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl <'info> AcceptOwner<'info> {
+    /// Protocol fees from later withdrawals go to the new owner.
+    pub fn accept_owner(&mut self)->Result<()>{
+        self.config.owner = self.new_owner.key();
+        self.config.pending_owner = None;
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+use crate::errors::ErrorCode;
+use crate::states::{ICOConfigAccount, ICOPurchaseAccount, ICOVaultAccount};
+use crate::utils::invoke_transfer_checked;
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+#[derive(Accounts)]
+pub struct ClaimVested<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    #[account()]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"ico_config_account", mint.key().as_ref()],
+        bump = ico_config_account.bump
+    )]
+    pub ico_config_account: Account<'info, ICOConfigAccount>,
+
+    #[account(
+        seeds = [b"ico_vault_account", mint.key().as_ref()],
+        bump = ico_vault_account.bump
+    )]
+    pub ico_vault_account: Account<'info, ICOVaultAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"ico_purchase_account", mint.key().as_ref(), buyer.key().as_ref()],
+        bump = ico_purchase_account.bump
+    )]
+    pub ico_purchase_account: Account<'info, ICOPurchaseAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = ico_vault_account,
+        associated_token::token_program = token_program
+    )]
+    pub vault_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = token_program
+    )]
+    pub buyer_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClaimVested<'info> {
+    pub fn claim_vested(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let vesting = self
+            .ico_config_account
+            .vesting
+            .ok_or(ErrorCode::VestingNotEnabled)?;
+        let current_time = Clock::get()?.unix_timestamp;
+
+        require!(
+            current_time > self.ico_config_account.end_time,
+            ErrorCode::ICOIsNotEnded
+        );
+        // A sale that missed its soft cap is settled through refunds instead
+        require!(
+            self.ico_config_account.total_raised >= self.ico_config_account.soft_cap,
+            ErrorCode::SoftCapNotReached
+        );
+
+        let elapsed = current_time - self.ico_config_account.end_time;
+        let unlocked = vesting
+            .unlocked_amount(self.ico_purchase_account.amount, elapsed)
+            .ok_or(ErrorCode::Overflow)?;
+        let claimable = unlocked
+            .checked_sub(self.ico_purchase_account.claimed)
+            .ok_or(ErrorCode::Overflow)?;
+
+        require!(claimable > 0, ErrorCode::NothingToClaim);
+
+        let mint_key = self.mint.key();
+        let seeds = &[
+            b"ico_vault_account",
+            mint_key.as_ref(),
+            &[self.ico_vault_account.bump],
+        ];
+        let signer_seeds: &[&[&[u8]]] = &[&seeds[..]];
+
+        invoke_transfer_checked(
+            self.token_program.key,
+            self.vault_ata.to_account_info(),
+            self.mint.to_account_info(),
+            self.buyer_ata.to_account_info(),
+            self.ico_vault_account.to_account_info(),
+            remaining_accounts,
+            claimable,
+            self.mint.decimals,
+            signer_seeds,
+        )?;
+
+        self.ico_purchase_account.claimed = unlocked;
+        self.ico_config_account.total_unclaimed = self
+            .ico_config_account
+            .total_unclaimed
+            .checked_sub(claimable)
+            .ok_or(ErrorCode::Overflow)?;
+
+        msg!(
+            "Claimed {} vested tokens ({} of {} unlocked)",
+            claimable,
+            unlocked,
+            self.ico_purchase_account.amount
+        );
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 use anchor_lang::prelude::*;
 
 use crate::states::Config;
+use crate::errors::ErrorCode;
 
 
 #[derive(Accounts)]
@@ -22,7 +23,8 @@ pub struct Initialize<'info>{
 
 impl <'info> Initialize<'info> {
     pub fn initialize(&mut self , _fee:u64, bumps:&InitializeBumps)->Result<()>{
-        self.config.set_inner(Config { owner: self.owner.key(), fee: _fee, bump:bumps.config});
+        require!(_fee <= Config::FEE_DENOMINATOR, ErrorCode::InvalidFee);
+        self.config.set_inner(Config { owner: self.owner.key(), fee: _fee, bump:bumps.config, pending_owner: None});
         Ok(())
     }
 }
@@ -1,6 +1,7 @@
 use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenInterface, TokenAccount}};
 use anchor_lang::prelude::*;
-use crate::states::{Config, ICOConfigAccount, ICOVaultAccount};
+use crate::states::{AllowlistPhase, Config, ICOConfigAccount, ICOEscrowAccount, ICOVaultAccount, VestingSchedule};
+use crate::utils::invoke_transfer_checked;
 use crate::errors::ErrorCode;
 
 
@@ -37,6 +38,15 @@ pub struct InitializeIco <'info>{
     )]
     pub ico_vault_account: Account<'info, ICOVaultAccount>,
 
+    #[account(
+        init,
+        payer = creator,
+        space = ICOEscrowAccount::DISCRIMINATOR.len() + ICOEscrowAccount::INIT_SPACE,
+        seeds = [b"ico_escrow_account", mint.key().as_ref()],
+        bump 
+    )]
+    pub ico_escrow_account: Account<'info, ICOEscrowAccount>,
+
     #[account(
         init,
         payer = creator,
@@ -56,18 +66,40 @@ pub struct InitializeIco <'info>{
 
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>
+    pub system_program: Program<'info, System>,
+
+    /// Set to sell for an SPL / Token-2022 currency instead of SOL
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = creator,
+        associated_token::mint = payment_mint,
+        associated_token::authority = ico_escrow_account,
+        associated_token::token_program = payment_token_program
+    )]
+    pub payment_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub payment_token_program: Option<Interface<'info, TokenInterface>>,
 }
 
 impl <'info> InitializeIco<'info>{
-    pub fn initialize_ico( &mut self, bumps: &InitializeIcoBumps, _soft_cap:u64, _hard_cap:u64 , _start_time:i64, _end_time:i64, _amount:u64, _price_per_token:u64, remaining_accounts: &[AccountInfo<'info>])-> Result<()>{
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_ico( &mut self, bumps: &InitializeIcoBumps, _soft_cap:u64, _hard_cap:u64 , _start_time:i64, _end_time:i64, _amount:u64, _price_per_token:u64, _min_purchase:u64, _max_purchase_per_wallet:u64, _vesting: Option<VestingSchedule>, _allowlist: Option<AllowlistPhase>, remaining_accounts: &[AccountInfo<'info>])-> Result<()>{
         self.initialize_ico_vault(bumps, _amount, remaining_accounts)?;
-        self.initialize_ico_account(bumps, _soft_cap, _hard_cap , _start_time, _end_time, _price_per_token)?;
+        self.initialize_ico_account(bumps, _soft_cap, _hard_cap , _start_time, _end_time, _price_per_token, _min_purchase, _max_purchase_per_wallet, _vesting, _allowlist)?;
+        self.initialize_ico_escrow(bumps)?;
+
+        require!(
+            self.payment_mint.is_none() || self.payment_vault.is_some(),
+            ErrorCode::MissingPaymentAccounts
+        );
         Ok(())
     }
 
 
-    pub fn initialize_ico_account(&mut self, bumps: &InitializeIcoBumps, _soft_cap:u64, _hard_cap:u64 , _start_time:i64, _end_time:i64, _price_per_token:u64)-> Result<()>{  
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_ico_account(&mut self, bumps: &InitializeIcoBumps, _soft_cap:u64, _hard_cap:u64 , _start_time:i64, _end_time:i64, _price_per_token:u64, _min_purchase:u64, _max_purchase_per_wallet:u64, _vesting: Option<VestingSchedule>, _allowlist: Option<AllowlistPhase>)-> Result<()>{  
         require!(_soft_cap <= _hard_cap, ErrorCode::SoftCapExceedsHardCap);
         require!(_hard_cap > 0, ErrorCode::CapCannotBeZero);
 
@@ -76,6 +108,22 @@ impl <'info> InitializeIco<'info>{
         require!(_start_time < _end_time, ErrorCode::StartTimeAfterEndTime);
         require!(_end_time > current_time, ErrorCode::EndTimeInPast);
 
+        require!(
+            _max_purchase_per_wallet == 0 || _min_purchase <= _max_purchase_per_wallet,
+            ErrorCode::InvalidPurchaseLimits
+        );
+
+        if let Some(vesting) = &_vesting {
+            require!(vesting.is_valid(), ErrorCode::InvalidVestingSchedule);
+        }
+
+        if let Some(allowlist) = &_allowlist {
+            require!(
+                allowlist.end_time >= _start_time && allowlist.end_time <= _end_time,
+                ErrorCode::InvalidAllowlistPhase
+            );
+        }
+
         self.ico_config_account.set_inner(ICOConfigAccount 
             { creator: self.creator.key(),
               mint: self.mint.key(), 
@@ -85,7 +133,16 @@ impl <'info> InitializeIco<'info>{
               end_time: _end_time, 
               token_vault: self.vault_ata.key(),
               total_raised: 0,
+              tokens_sold: 0,
+              legacy_tokens_sold: 0,
               price_per_token: _price_per_token,
+              min_purchase: _min_purchase,
+              max_purchase_per_wallet: _max_purchase_per_wallet,
+              vesting: _vesting,
+              allowlist: _allowlist,
+              tiered: false,
+              payment_mint: self.payment_mint.as_ref().map(|payment_mint| payment_mint.key()),
+              total_unclaimed: 0,
               bump: bumps.ico_config_account });
         Ok(())
     }
@@ -97,7 +154,7 @@ impl <'info> InitializeIco<'info>{
             amount: _amount, 
             bump: _bumps.ico_vault_account });
 
-            spl_token_2022::onchain::invoke_transfer_checked(
+            invoke_transfer_checked(
                 self.token_program.key,
                 self.creator_ata.to_account_info(),
                 self.mint.to_account_info(),
@@ -110,4 +167,12 @@ impl <'info> InitializeIco<'info>{
             )?;
         Ok(())
     }
+
+    pub fn initialize_ico_escrow(&mut self, bumps: &InitializeIcoBumps)-> Result<()>{
+        self.ico_escrow_account.set_inner(ICOEscrowAccount {
+            mint: self.mint.key(),
+            creator: self.creator.key(),
+            bump: bumps.ico_escrow_account });
+        Ok(())
+    }
 }
\ No newline at end of file
@@ -0,0 +1,68 @@
+use crate::errors::ErrorCode;
+use crate::states::{ICOConfigAccount, ICOTiersAccount, SaleTier, MAX_SALE_TIERS};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+#[derive(Accounts)]
+pub struct InitializeTiers<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account()]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"ico_config_account", mint.key().as_ref()],
+        bump = ico_config_account.bump,
+        has_one = creator @ ErrorCode::CreatorMismatch
+    )]
+    pub ico_config_account: Account<'info, ICOConfigAccount>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = ICOTiersAccount::DISCRIMINATOR.len() + ICOTiersAccount::INIT_SPACE,
+        seeds = [b"ico_tiers_account", mint.key().as_ref()],
+        bump
+    )]
+    pub ico_tiers_account: Account<'info, ICOTiersAccount>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeTiers<'info> {
+    pub fn initialize_tiers(&mut self, tiers: Vec<SaleTier>, bumps: &InitializeTiersBumps) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
+        let ico = &self.ico_config_account;
+
+        require!(current_time < ico.start_time, ErrorCode::ICOIsAlreadyStarted);
+        require!(
+            !tiers.is_empty() && tiers.len() <= MAX_SALE_TIERS,
+            ErrorCode::InvalidSaleTiers
+        );
+
+        let mut previous_start = ico.start_time;
+        for tier in tiers.iter() {
+            require!(tier.price_per_token > 0, ErrorCode::PricePerTokenCannotBeZero);
+            require!(
+                tier.cap > 0
+                    && tier.sold == 0
+                    && tier.start_time < tier.end_time
+                    && tier.start_time >= previous_start
+                    && tier.end_time <= ico.end_time,
+                ErrorCode::InvalidSaleTiers
+            );
+            previous_start = tier.start_time;
+        }
+
+        self.ico_tiers_account.set_inner(ICOTiersAccount {
+            mint: self.mint.key(),
+            tiers,
+            bump: bumps.ico_tiers_account,
+        });
+        self.ico_config_account.tiered = true;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+use anchor_lang::prelude::*;
+
+use crate::states::{Config, LegacyConfig};
+use crate::errors::ErrorCode;
+use crate::utils::rewrite_account;
+
+
+/// Upgrades the protocol config created before two-step ownership transfers,
+/// appending an empty `pending_owner`.
+#[derive(Accounts)]
+pub struct MigrateConfig<'info>{
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: Deserialized by hand, as the legacy layout no longer matches `Config`
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl <'info> MigrateConfig<'info> {
+    pub fn migrate_config(&mut self)->Result<()>{
+        let info = self.config.to_account_info();
+        let legacy_len = Config::DISCRIMINATOR.len() + LegacyConfig::LEN;
+
+        let legacy = {
+            let data = info.try_borrow_data()?;
+            require!(
+                info.owner == &crate::ID
+                    && data.len() == legacy_len
+                    && data.starts_with(Config::DISCRIMINATOR),
+                ErrorCode::NotLegacyConfig
+            );
+            LegacyConfig::deserialize(&mut &data[Config::DISCRIMINATOR.len()..])?
+        };
+
+        require_keys_eq!(legacy.owner, self.owner.key(), ErrorCode::ProtocolOwnerMismatch);
+
+        let migrated = Config {
+            owner: legacy.owner,
+            fee: legacy.fee,
+            bump: legacy.bump,
+            pending_owner: None,
+        };
+
+        let new_len = Config::DISCRIMINATOR.len() + Config::INIT_SPACE;
+        rewrite_account(
+            &info,
+            &migrated,
+            new_len,
+            &self.owner.to_account_info(),
+            &self.system_program.to_account_info(),
+        )?;
+
+        Ok(())
+    }
+}
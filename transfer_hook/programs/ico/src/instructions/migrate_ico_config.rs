@@ -0,0 +1,115 @@
+use crate::errors::ErrorCode;
+use crate::states::{ICOConfigAccount, ICOEscrowAccount, LegacyICOConfigAccount};
+use crate::utils::rewrite_account;
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+/// Upgrades an ICO created before payment and token totals were split.
+///
+/// Legacy sales counted tokens in `total_raised` and compared the caps against
+/// that count, so tokens sold, raised amount and caps are all converted at the
+/// sale's price, and the price itself is rescaled from lamports per base unit
+/// to the fixed-point price per whole token. Proceeds from legacy purchases
+/// were paid straight to the creator, so only purchases made after the
+/// migration go through escrow. Legacy buyers cannot refund if the sale misses
+/// its soft cap; their tokens are kept in `legacy_tokens_sold` so the vault can
+/// still be closed.
+#[derive(Accounts)]
+pub struct MigrateIcoConfig<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account()]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Deserialized by hand, as the legacy layout no longer matches `ICOConfigAccount`
+    #[account(
+        mut,
+        seeds = [b"ico_config_account", mint.key().as_ref()],
+        bump
+    )]
+    pub ico_config_account: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = creator,
+        space = ICOEscrowAccount::DISCRIMINATOR.len() + ICOEscrowAccount::INIT_SPACE,
+        seeds = [b"ico_escrow_account", mint.key().as_ref()],
+        bump
+    )]
+    pub ico_escrow_account: Account<'info, ICOEscrowAccount>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigrateIcoConfig<'info> {
+    pub fn migrate_ico_config(&mut self, bumps: &MigrateIcoConfigBumps) -> Result<()> {
+        let info = self.ico_config_account.to_account_info();
+        let legacy_len = ICOConfigAccount::DISCRIMINATOR.len() + LegacyICOConfigAccount::LEN;
+
+        let legacy = {
+            let data = info.try_borrow_data()?;
+            require!(
+                info.owner == &crate::ID
+                    && data.len() == legacy_len
+                    && data.starts_with(ICOConfigAccount::DISCRIMINATOR),
+                ErrorCode::NotLegacyICOConfig
+            );
+            LegacyICOConfigAccount::deserialize(&mut &data[ICOConfigAccount::DISCRIMINATOR.len()..])?
+        };
+
+        require_keys_eq!(legacy.creator, self.creator.key(), ErrorCode::CreatorMismatch);
+
+        let to_lamports = |tokens: u64| {
+            tokens
+                .checked_mul(legacy.price_per_token)
+                .ok_or(ErrorCode::Overflow)
+        };
+        let migrated = ICOConfigAccount {
+            creator: legacy.creator,
+            mint: legacy.mint,
+            soft_cap: to_lamports(legacy.soft_cap)?,
+            hard_cap: to_lamports(legacy.hard_cap)?,
+            start_time: legacy.start_time,
+            end_time: legacy.end_time,
+            token_vault: legacy.token_vault,
+            total_raised: to_lamports(legacy.total_raised)?,
+            tokens_sold: legacy.total_raised,
+            legacy_tokens_sold: legacy.total_raised,
+            price_per_token: 10u64
+                .checked_pow(self.mint.decimals as u32)
+                .and_then(|scale| legacy.price_per_token.checked_mul(scale))
+                .ok_or(ErrorCode::Overflow)?,
+            min_purchase: 0,
+            max_purchase_per_wallet: 0,
+            vesting: None,
+            allowlist: None,
+            tiered: false,
+            payment_mint: None,
+            total_unclaimed: 0,
+            bump: legacy.bump,
+        };
+
+        let new_len = ICOConfigAccount::DISCRIMINATOR.len() + ICOConfigAccount::INIT_SPACE;
+        rewrite_account(
+            &info,
+            &migrated,
+            new_len,
+            &self.creator.to_account_info(),
+            &self.system_program.to_account_info(),
+        )?;
+
+        self.ico_escrow_account.set_inner(ICOEscrowAccount {
+            mint: legacy.mint,
+            creator: legacy.creator,
+            bump: bumps.ico_escrow_account,
+        });
+
+        msg!(
+            "ICO config migrated: {} tokens sold, {} lamports raised",
+            migrated.tokens_sold,
+            migrated.total_raised
+        );
+        Ok(())
+    }
+}
@@ -1,7 +1,25 @@
+pub mod accept_owner;
+pub mod claim_vested;
 pub mod initialize;
 pub mod initialize_ico;
+pub mod initialize_tiers;
+pub mod migrate_config;
+pub mod migrate_ico_config;
+pub mod propose_owner;
 pub mod purchase_token;
+pub mod reclaim_unsold;
+pub mod refund;
+pub mod withdraw_proceeds;
 
+pub use accept_owner::*;
+pub use claim_vested::*;
 pub use initialize::*;
 pub use initialize_ico::*;
+pub use initialize_tiers::*;
+pub use migrate_config::*;
+pub use migrate_ico_config::*;
+pub use propose_owner::*;
 pub use purchase_token::*;
+pub use reclaim_unsold::*;
+pub use refund::*;
+pub use withdraw_proceeds::*;
@@ -0,0 +1,25 @@
+use anchor_lang::prelude::*;
+
+use crate::states::Config;
+use crate::errors::ErrorCode;
+
+
+#[derive(Accounts)]
+pub struct ProposeOwner<'info>{
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = owner @ ErrorCode::ProtocolOwnerMismatch
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl <'info> ProposeOwner<'info> {
+    pub fn propose_owner(&mut self, new_owner: Pubkey)->Result<()>{
+        self.config.pending_owner = Some(new_owner);
+        Ok(())
+    }
+}
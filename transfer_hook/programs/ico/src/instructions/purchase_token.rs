@@ -1,10 +1,13 @@
 use crate::errors::ErrorCode;
-use crate::states::{Config, ICOConfigAccount, ICOVaultAccount};
+use crate::states::{
+    Config, ICOConfigAccount, ICOEscrowAccount, ICOPurchaseAccount, ICOTiersAccount, ICOVaultAccount,
+};
+use crate::utils::invoke_transfer_checked;
 use anchor_lang::prelude::*;
 use anchor_lang::system_program::{transfer, Transfer};
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{Mint, TokenAccount, TokenInterface},
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
 };
 
 #[derive(Accounts)]
@@ -21,13 +24,6 @@ pub struct PurchaseToken<'info> {
     )]
     pub config: Account<'info, Config>,
 
-    /// CHECK: Validated via address constraint to ensure SOL goes to the correct creator
-    #[account(
-        mut,
-        address = ico_config_account.creator @ ErrorCode::CreatorMismatch
-    )]
-    pub creator: AccountInfo<'info>,
-
     #[account(
         mut,
         seeds = [b"ico_config_account", mint.key().as_ref()],
@@ -41,6 +37,29 @@ pub struct PurchaseToken<'info> {
     )]
     pub ico_vault_account: Account<'info, ICOVaultAccount>,
 
+    #[account(
+        mut,
+        seeds = [b"ico_escrow_account", mint.key().as_ref()],
+        bump = ico_escrow_account.bump
+    )]
+    pub ico_escrow_account: Account<'info, ICOEscrowAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        space = ICOPurchaseAccount::DISCRIMINATOR.len() + ICOPurchaseAccount::INIT_SPACE,
+        seeds = [b"ico_purchase_account", mint.key().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub ico_purchase_account: Account<'info, ICOPurchaseAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"ico_tiers_account", mint.key().as_ref()],
+        bump = ico_tiers_account.bump
+    )]
+    pub ico_tiers_account: Option<Account<'info, ICOTiersAccount>>,
+
     #[account(
         mut,
         associated_token::mint = mint,
@@ -61,10 +80,40 @@ pub struct PurchaseToken<'info> {
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
+
+    #[account(
+        address = ico_config_account.payment_mint.unwrap_or_default() @ ErrorCode::PaymentMintMismatch
+    )]
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        token::mint = payment_mint,
+        token::authority = buyer,
+        token::token_program = payment_token_program
+    )]
+    pub buyer_payment_ata: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = payment_mint,
+        associated_token::authority = ico_escrow_account,
+        associated_token::token_program = payment_token_program
+    )]
+    pub payment_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub payment_token_program: Option<Interface<'info, TokenInterface>>,
 }
 
 impl<'info> PurchaseToken<'info> {
-    pub fn purchase_token(&mut self, amount: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+    pub fn purchase_token(
+        &mut self,
+        amount: u64,
+        proof: &[[u8; 32]],
+        allocation: Option<u64>,
+        bumps: &PurchaseTokenBumps,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
         let current_time = Clock::get()?.unix_timestamp;
 
         require!(
@@ -73,59 +122,171 @@ impl<'info> PurchaseToken<'info> {
             ErrorCode::ICOIsNotActive
         );
 
-        let total_sol_cost = amount
-            .checked_mul(self.ico_config_account.price_per_token)
+        require!(
+            amount >= self.ico_config_account.min_purchase,
+            ErrorCode::BelowMinPurchase
+        );
+        let new_wallet_total = self
+            .ico_purchase_account
+            .amount
+            .checked_add(amount)
             .ok_or(ErrorCode::Overflow)?;
+        require!(
+            self.ico_config_account.max_purchase_per_wallet == 0
+                || new_wallet_total <= self.ico_config_account.max_purchase_per_wallet,
+            ErrorCode::ExceedsMaxPurchasePerWallet
+        );
+
+        if let Some(allowlist) = &self.ico_config_account.allowlist {
+            if current_time < allowlist.end_time {
+                require!(
+                    allowlist.verify(&self.buyer.key(), allocation, proof),
+                    ErrorCode::NotAllowlisted
+                );
+                if let Some(allocation) = allocation {
+                    require!(new_wallet_total <= allocation, ErrorCode::ExceedsAllocation);
+                }
+            }
+        }
+
+        let quote = if self.ico_config_account.tiered {
+            self.ico_tiers_account
+                .as_mut()
+                .ok_or(ErrorCode::MissingSaleTiers)?
+                .fill(amount, current_time)?
+        } else {
+            (amount as u128)
+                .checked_mul(self.ico_config_account.price_per_token as u128)
+                .ok_or(ErrorCode::Overflow)?
+        };
+        let payment_amount = self.payment_amount(quote)?;
         let new_total_raised = self
             .ico_config_account
             .total_raised
-            .checked_add(amount)
+            .checked_add(payment_amount)
             .ok_or(ErrorCode::Overflow)?;
 
         require!(
             new_total_raised <= self.ico_config_account.hard_cap,
             ErrorCode::ICOHardCapReached
         );
-        require!(self.vault_ata.amount >= amount, ErrorCode::NotEnoughTokens);
-        require!(
-            self.buyer.lamports() >= total_sol_cost,
-            ErrorCode::NotEnoughSOL
-        );
+        let available_tokens = self
+            .vault_ata
+            .amount
+            .checked_sub(self.ico_config_account.total_unclaimed)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(available_tokens >= amount, ErrorCode::NotEnoughTokens);
 
-        let accounts_sol = Transfer {
-            from: self.buyer.to_account_info(),
-            to: self.creator.to_account_info(),
-        };
-        let ctx_sol = CpiContext::new(self.system_program.to_account_info(), accounts_sol);
-        transfer(ctx_sol, total_sol_cost)?;
+        self.collect_payment(payment_amount)?;
 
         let mint_key = self.mint.key();
-        let seeds = &[
-            b"ico_vault_account",
-            mint_key.as_ref(),
-            &[self.ico_vault_account.bump],
-        ];
-        let signer_seeds: &[&[&[u8]]] = &[&seeds[..]];
-
-        spl_token_2022::onchain::invoke_transfer_checked(
-            self.token_program.key,
-            self.vault_ata.to_account_info(),
-            self.mint.to_account_info(),
-            self.buyer_ata.to_account_info(),
-            self.ico_vault_account.to_account_info(),
-            remaining_accounts,
-            amount,
-            self.mint.decimals,
-            signer_seeds,
-        )?;
+        let delivered = if self.ico_config_account.vesting.is_some() {
+            // Vested purchases stay in the vault until claimed
+            self.ico_config_account.total_unclaimed = self
+                .ico_config_account
+                .total_unclaimed
+                .checked_add(amount)
+                .ok_or(ErrorCode::Overflow)?;
+            0
+        } else {
+            let seeds = &[
+                b"ico_vault_account",
+                mint_key.as_ref(),
+                &[self.ico_vault_account.bump],
+            ];
+            let signer_seeds: &[&[&[u8]]] = &[&seeds[..]];
+
+            invoke_transfer_checked(
+                self.token_program.key,
+                self.vault_ata.to_account_info(),
+                self.mint.to_account_info(),
+                self.buyer_ata.to_account_info(),
+                self.ico_vault_account.to_account_info(),
+                remaining_accounts,
+                amount,
+                self.mint.decimals,
+                signer_seeds,
+            )?;
+            amount
+        };
 
         self.ico_config_account.total_raised = new_total_raised;
+        self.ico_config_account.tokens_sold = self
+            .ico_config_account
+            .tokens_sold
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        let purchase = &mut self.ico_purchase_account;
+        if purchase.buyer == Pubkey::default() {
+            purchase.buyer = self.buyer.key();
+            purchase.mint = mint_key;
+            purchase.bump = bumps.ico_purchase_account;
+        }
+        purchase.amount = new_wallet_total;
+        purchase.amount_paid = purchase
+            .amount_paid
+            .checked_add(payment_amount)
+            .ok_or(ErrorCode::Overflow)?;
+        purchase.claimed = purchase.claimed.checked_add(delivered).ok_or(ErrorCode::Overflow)?;
 
         msg!(
-            "Purchase successful: {} tokens for {} lamports",
+            "Purchase successful: {} tokens for {} payment units",
             amount,
-            total_sol_cost
+            payment_amount
         );
         Ok(())
     }
+
+    /// Converts a quote into the units buyers actually pay: lamports, or
+    /// base units of the payment mint.
+    fn payment_amount(&self, quote: u128) -> Result<u64> {
+        let payment_decimals = if self.ico_config_account.payment_mint.is_none() {
+            ICOConfigAccount::PRICE_DECIMALS
+        } else {
+            self.payment_mint
+                .as_ref()
+                .ok_or(ErrorCode::MissingPaymentAccounts)?
+                .decimals
+        };
+        Ok(ICOConfigAccount::to_payment_units(quote, self.mint.decimals, payment_decimals)
+            .ok_or(ErrorCode::Overflow)?)
+    }
+
+    /// Moves `amount` payment units from the buyer into escrow.
+    fn collect_payment(&self, amount: u64) -> Result<()> {
+        if self.ico_config_account.payment_mint.is_none() {
+            require!(self.buyer.lamports() >= amount, ErrorCode::NotEnoughSOL);
+
+            let accounts_sol = Transfer {
+                from: self.buyer.to_account_info(),
+                to: self.ico_escrow_account.to_account_info(),
+            };
+            let ctx_sol = CpiContext::new(self.system_program.to_account_info(), accounts_sol);
+            return transfer(ctx_sol, amount);
+        }
+
+        let (Some(payment_mint), Some(buyer_payment_ata), Some(payment_vault), Some(payment_token_program)) = (
+            &self.payment_mint,
+            &self.buyer_payment_ata,
+            &self.payment_vault,
+            &self.payment_token_program,
+        ) else {
+            return err!(ErrorCode::MissingPaymentAccounts);
+        };
+
+        require!(
+            buyer_payment_ata.amount >= amount,
+            ErrorCode::NotEnoughPaymentTokens
+        );
+
+        let accounts = TransferChecked {
+            from: buyer_payment_ata.to_account_info(),
+            mint: payment_mint.to_account_info(),
+            to: payment_vault.to_account_info(),
+            authority: self.buyer.to_account_info(),
+        };
+        let ctx = CpiContext::new(payment_token_program.to_account_info(), accounts);
+        transfer_checked(ctx, amount, payment_mint.decimals)
+    }
 }
@@ -0,0 +1,122 @@
+use crate::errors::ErrorCode;
+use crate::states::{ICOConfigAccount, ICOVaultAccount};
+use crate::utils::invoke_transfer_checked;
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{close_account, CloseAccount, Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct ReclaimUnsold<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account()]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"ico_config_account", mint.key().as_ref()],
+        bump = ico_config_account.bump,
+        has_one = creator @ ErrorCode::CreatorMismatch
+    )]
+    pub ico_config_account: Account<'info, ICOConfigAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"ico_vault_account", mint.key().as_ref()],
+        bump = ico_vault_account.bump
+    )]
+    pub ico_vault_account: Account<'info, ICOVaultAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = ico_vault_account,
+        associated_token::token_program = token_program
+    )]
+    pub vault_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = creator,
+        associated_token::token_program = token_program
+    )]
+    pub creator_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> ReclaimUnsold<'info> {
+    pub fn reclaim_unsold(
+        &mut self,
+        close_vault: bool,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
+
+        require!(
+            current_time > self.ico_config_account.end_time,
+            ErrorCode::ICOIsNotEnded
+        );
+
+        let mint_key = self.mint.key();
+        let seeds = &[
+            b"ico_vault_account",
+            mint_key.as_ref(),
+            &[self.ico_vault_account.bump],
+        ];
+        let signer_seeds: &[&[&[u8]]] = &[&seeds[..]];
+
+        // Tokens owed to vesting buyers are not part of the unsold balance
+        let unsold_amount = self
+            .vault_ata
+            .amount
+            .checked_sub(self.ico_config_account.total_unclaimed)
+            .ok_or(ErrorCode::Overflow)?;
+        if unsold_amount > 0 {
+            invoke_transfer_checked(
+                self.token_program.key,
+                self.vault_ata.to_account_info(),
+                self.mint.to_account_info(),
+                self.creator_ata.to_account_info(),
+                self.ico_vault_account.to_account_info(),
+                remaining_accounts,
+                unsold_amount,
+                self.mint.decimals,
+                signer_seeds,
+            )?;
+        }
+
+        if close_vault {
+            // Refunds and vesting claims both go through the vault, so it must outlive them.
+            // Legacy buyers were never escrowed and cannot refund, so they are not waited on
+            let ico = &self.ico_config_account;
+            let settled = if ico.total_raised >= ico.soft_cap {
+                ico.total_unclaimed == 0
+            } else {
+                ico.tokens_sold == ico.legacy_tokens_sold
+            };
+            require!(settled, ErrorCode::VaultCloseNotAllowed);
+
+            let accounts = CloseAccount {
+                account: self.vault_ata.to_account_info(),
+                destination: self.creator.to_account_info(),
+                authority: self.ico_vault_account.to_account_info(),
+            };
+            let ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                accounts,
+                signer_seeds,
+            );
+            close_account(ctx)?;
+
+            self.ico_vault_account.close(self.creator.to_account_info())?;
+        }
+
+        msg!(
+            "Reclaimed {} unsold tokens, vault closed: {}",
+            unsold_amount,
+            close_vault
+        );
+        Ok(())
+    }
+}
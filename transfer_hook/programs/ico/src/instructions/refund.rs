@@ -0,0 +1,183 @@
+use crate::errors::ErrorCode;
+use crate::states::{ICOConfigAccount, ICOEscrowAccount, ICOPurchaseAccount, ICOVaultAccount};
+use crate::utils::invoke_transfer_checked;
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked};
+
+#[derive(Accounts)]
+pub struct Refund<'info> {
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+
+    #[account()]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"ico_config_account", mint.key().as_ref()],
+        bump = ico_config_account.bump
+    )]
+    pub ico_config_account: Account<'info, ICOConfigAccount>,
+
+    #[account(
+        seeds = [b"ico_vault_account", mint.key().as_ref()],
+        bump = ico_vault_account.bump
+    )]
+    pub ico_vault_account: Account<'info, ICOVaultAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"ico_escrow_account", mint.key().as_ref()],
+        bump = ico_escrow_account.bump
+    )]
+    pub ico_escrow_account: Account<'info, ICOEscrowAccount>,
+
+    #[account(
+        mut,
+        close = buyer,
+        seeds = [b"ico_purchase_account", mint.key().as_ref(), buyer.key().as_ref()],
+        bump = ico_purchase_account.bump
+    )]
+    pub ico_purchase_account: Account<'info, ICOPurchaseAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = ico_vault_account,
+        associated_token::token_program = token_program
+    )]
+    pub vault_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = buyer,
+        associated_token::token_program = token_program
+    )]
+    pub buyer_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    #[account(
+        address = ico_config_account.payment_mint.unwrap_or_default() @ ErrorCode::PaymentMintMismatch
+    )]
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        token::mint = payment_mint,
+        token::authority = buyer,
+        token::token_program = payment_token_program
+    )]
+    pub buyer_payment_ata: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = payment_mint,
+        associated_token::authority = ico_escrow_account,
+        associated_token::token_program = payment_token_program
+    )]
+    pub payment_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub payment_token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+impl<'info> Refund<'info> {
+    pub fn refund(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
+
+        require!(
+            current_time > self.ico_config_account.end_time,
+            ErrorCode::ICOIsNotEnded
+        );
+        require!(
+            self.ico_config_account.total_raised < self.ico_config_account.soft_cap,
+            ErrorCode::SoftCapReached
+        );
+
+        // Only tokens already delivered to the buyer need to come back; vested
+        // tokens that were never claimed are still sitting in the vault.
+        let token_amount = self.ico_purchase_account.claimed;
+        let unclaimed_amount = self
+            .ico_purchase_account
+            .amount
+            .checked_sub(token_amount)
+            .ok_or(ErrorCode::Overflow)?;
+        let refund_amount = self.ico_purchase_account.amount_paid;
+
+        if token_amount > 0 {
+            require!(
+                self.buyer_ata.amount >= token_amount,
+                ErrorCode::NotEnoughTokens
+            );
+
+            invoke_transfer_checked(
+                self.token_program.key,
+                self.buyer_ata.to_account_info(),
+                self.mint.to_account_info(),
+                self.vault_ata.to_account_info(),
+                self.buyer.to_account_info(),
+                remaining_accounts,
+                token_amount,
+                self.mint.decimals,
+                &[],
+            )?;
+        }
+
+        self.ico_config_account.total_unclaimed = self
+            .ico_config_account
+            .total_unclaimed
+            .checked_sub(unclaimed_amount)
+            .ok_or(ErrorCode::Overflow)?;
+        // Refunded tokens count as unsold again, so the vault can close once every buyer is out
+        self.ico_config_account.tokens_sold = self
+            .ico_config_account
+            .tokens_sold
+            .checked_sub(self.ico_purchase_account.amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        self.return_payment(refund_amount)?;
+
+        msg!(
+            "Refund successful: {} tokens returned for {} payment units",
+            token_amount,
+            refund_amount
+        );
+        Ok(())
+    }
+
+    /// Returns `amount` from escrow to the buyer, in SOL or the configured payment mint.
+    fn return_payment(&self, amount: u64) -> Result<()> {
+        if self.ico_config_account.payment_mint.is_none() {
+            self.ico_escrow_account.sub_lamports(amount)?;
+            self.buyer.add_lamports(amount)?;
+            return Ok(());
+        }
+
+        let (Some(payment_mint), Some(buyer_payment_ata), Some(payment_vault), Some(payment_token_program)) = (
+            &self.payment_mint,
+            &self.buyer_payment_ata,
+            &self.payment_vault,
+            &self.payment_token_program,
+        ) else {
+            return err!(ErrorCode::MissingPaymentAccounts);
+        };
+
+        let mint_key = self.mint.key();
+        let seeds = &[
+            b"ico_escrow_account",
+            mint_key.as_ref(),
+            &[self.ico_escrow_account.bump],
+        ];
+        let signer_seeds: &[&[&[u8]]] = &[&seeds[..]];
+
+        let accounts = TransferChecked {
+            from: payment_vault.to_account_info(),
+            mint: payment_mint.to_account_info(),
+            to: buyer_payment_ata.to_account_info(),
+            authority: self.ico_escrow_account.to_account_info(),
+        };
+        let ctx = CpiContext::new_with_signer(payment_token_program.to_account_info(), accounts, signer_seeds);
+        transfer_checked(ctx, amount, payment_mint.decimals)
+    }
+}
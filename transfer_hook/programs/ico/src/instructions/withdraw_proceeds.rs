@@ -0,0 +1,180 @@
+use crate::errors::ErrorCode;
+use crate::states::{Config, ICOConfigAccount, ICOEscrowAccount};
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+
+#[derive(Accounts)]
+pub struct WithdrawProceeds<'info> {
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    #[account()]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: Validated via address constraint to ensure the fee goes to the protocol owner
+    #[account(
+        mut,
+        address = config.owner @ ErrorCode::ProtocolOwnerMismatch
+    )]
+    pub protocol_owner: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"ico_config_account", mint.key().as_ref()],
+        bump = ico_config_account.bump,
+        has_one = creator @ ErrorCode::CreatorMismatch
+    )]
+    pub ico_config_account: Account<'info, ICOConfigAccount>,
+
+    #[account(
+        mut,
+        close = creator,
+        seeds = [b"ico_escrow_account", mint.key().as_ref()],
+        bump = ico_escrow_account.bump
+    )]
+    pub ico_escrow_account: Account<'info, ICOEscrowAccount>,
+
+    #[account(
+        address = ico_config_account.payment_mint.unwrap_or_default() @ ErrorCode::PaymentMintMismatch
+    )]
+    pub payment_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = payment_mint,
+        associated_token::authority = ico_escrow_account,
+        associated_token::token_program = payment_token_program
+    )]
+    pub payment_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = payment_mint,
+        token::authority = creator,
+        token::token_program = payment_token_program
+    )]
+    pub creator_payment_ata: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = payment_mint,
+        token::authority = protocol_owner,
+        token::token_program = payment_token_program
+    )]
+    pub protocol_owner_payment_ata: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub payment_token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+impl<'info> WithdrawProceeds<'info> {
+    pub fn withdraw_proceeds(&mut self) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
+
+        require!(
+            current_time > self.ico_config_account.end_time,
+            ErrorCode::ICOIsNotEnded
+        );
+        require!(
+            self.ico_config_account.total_raised >= self.ico_config_account.soft_cap,
+            ErrorCode::SoftCapNotReached
+        );
+
+        if self.ico_config_account.payment_mint.is_some() {
+            return self.withdraw_token_proceeds();
+        }
+
+        let escrow_info = self.ico_escrow_account.to_account_info();
+        let rent_exempt_minimum = Rent::get()?.minimum_balance(escrow_info.data_len());
+        let proceeds = escrow_info
+            .lamports()
+            .checked_sub(rent_exempt_minimum)
+            .ok_or(ErrorCode::Overflow)?;
+        let fee_amount = self.fee_for(proceeds)?;
+        let creator_amount = proceeds.checked_sub(fee_amount).ok_or(ErrorCode::Overflow)?;
+
+        self.ico_escrow_account.sub_lamports(fee_amount)?;
+        self.protocol_owner.add_lamports(fee_amount)?;
+
+        msg!(
+            "Proceeds withdrawn: {} lamports to creator, {} lamports protocol fee",
+            creator_amount,
+            fee_amount
+        );
+        Ok(())
+    }
+
+    fn withdraw_token_proceeds(&mut self) -> Result<()> {
+        let (
+            Some(payment_mint),
+            Some(payment_vault),
+            Some(creator_payment_ata),
+            Some(protocol_owner_payment_ata),
+            Some(payment_token_program),
+        ) = (
+            &self.payment_mint,
+            &self.payment_vault,
+            &self.creator_payment_ata,
+            &self.protocol_owner_payment_ata,
+            &self.payment_token_program,
+        ) else {
+            return err!(ErrorCode::MissingPaymentAccounts);
+        };
+
+        let proceeds = payment_vault.amount;
+        let fee_amount = self.fee_for(proceeds)?;
+        let creator_amount = proceeds.checked_sub(fee_amount).ok_or(ErrorCode::Overflow)?;
+
+        let mint_key = self.mint.key();
+        let seeds = &[
+            b"ico_escrow_account",
+            mint_key.as_ref(),
+            &[self.ico_escrow_account.bump],
+        ];
+        let signer_seeds: &[&[&[u8]]] = &[&seeds[..]];
+
+        for (destination, amount) in [
+            (protocol_owner_payment_ata, fee_amount),
+            (creator_payment_ata, creator_amount),
+        ] {
+            let accounts = TransferChecked {
+                from: payment_vault.to_account_info(),
+                mint: payment_mint.to_account_info(),
+                to: destination.to_account_info(),
+                authority: self.ico_escrow_account.to_account_info(),
+            };
+            let ctx = CpiContext::new_with_signer(payment_token_program.to_account_info(), accounts, signer_seeds);
+            transfer_checked(ctx, amount, payment_mint.decimals)?;
+        }
+
+        let accounts = CloseAccount {
+            account: payment_vault.to_account_info(),
+            destination: self.creator.to_account_info(),
+            authority: self.ico_escrow_account.to_account_info(),
+        };
+        let ctx = CpiContext::new_with_signer(payment_token_program.to_account_info(), accounts, signer_seeds);
+        close_account(ctx)?;
+
+        msg!(
+            "Proceeds withdrawn: {} payment tokens to creator, {} payment tokens protocol fee",
+            creator_amount,
+            fee_amount
+        );
+        Ok(())
+    }
+
+    fn fee_for(&self, proceeds: u64) -> Result<u64> {
+        let fee_amount = (proceeds as u128)
+            .checked_mul(self.config.fee as u128)
+            .ok_or(ErrorCode::Overflow)?
+            .checked_div(Config::FEE_DENOMINATOR as u128)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(fee_amount as u64)
+    }
+}
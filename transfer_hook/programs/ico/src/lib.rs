@@ -3,7 +3,9 @@ use anchor_lang::prelude::*;
 pub mod errors;
 pub mod states;
 pub mod instructions;
+pub mod utils;
 pub use instructions::*;
+use states::{AllowlistPhase, SaleTier, VestingSchedule};
 
 declare_id!("3YXfnw8Lk1PsuwbyRxSjHHVwxxDLiDH1BHohgbZcW4zb");
 
@@ -16,13 +18,59 @@ pub mod ico {
        Ok(())
     }
 
-    pub fn initialize_ico<'info>(ctx: Context<'_, '_, '_, 'info, InitializeIco<'info>>, soft_cap: u64, hard_cap: u64, start_time: i64, end_time: i64, amount: u64, price_per_token: u64) -> Result<()> {
-        ctx.accounts.initialize_ico(&ctx.bumps, soft_cap, hard_cap, start_time, end_time, amount, price_per_token, ctx.remaining_accounts)?;
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_ico<'info>(ctx: Context<'_, '_, '_, 'info, InitializeIco<'info>>, soft_cap: u64, hard_cap: u64, start_time: i64, end_time: i64, amount: u64, price_per_token: u64, min_purchase: u64, max_purchase_per_wallet: u64, vesting: Option<VestingSchedule>, allowlist: Option<AllowlistPhase>) -> Result<()> {
+        ctx.accounts.initialize_ico(&ctx.bumps, soft_cap, hard_cap, start_time, end_time, amount, price_per_token, min_purchase, max_purchase_per_wallet, vesting, allowlist, ctx.remaining_accounts)?;
         Ok(())
     }
 
-    pub fn purchase_token<'info>(ctx: Context<'_, '_, '_, 'info, PurchaseToken<'info>>, amount: u64) -> Result<()> {
-        ctx.accounts.purchase_token(amount, ctx.remaining_accounts)?;
+    pub fn initialize_tiers(ctx: Context<InitializeTiers>, tiers: Vec<SaleTier>) -> Result<()> {
+        ctx.accounts.initialize_tiers(tiers, &ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn purchase_token<'info>(ctx: Context<'_, '_, '_, 'info, PurchaseToken<'info>>, amount: u64, proof: Vec<[u8; 32]>, allocation: Option<u64>) -> Result<()> {
+        ctx.accounts.purchase_token(amount, &proof, allocation, &ctx.bumps, ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn refund<'info>(ctx: Context<'_, '_, '_, 'info, Refund<'info>>) -> Result<()> {
+        ctx.accounts.refund(ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn withdraw_proceeds(ctx: Context<WithdrawProceeds>) -> Result<()> {
+        ctx.accounts.withdraw_proceeds()?;
+        Ok(())
+    }
+
+    pub fn reclaim_unsold<'info>(ctx: Context<'_, '_, '_, 'info, ReclaimUnsold<'info>>, close_vault: bool) -> Result<()> {
+        ctx.accounts.reclaim_unsold(close_vault, ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn claim_vested<'info>(ctx: Context<'_, '_, '_, 'info, ClaimVested<'info>>) -> Result<()> {
+        ctx.accounts.claim_vested(ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn migrate_ico_config(ctx: Context<MigrateIcoConfig>) -> Result<()> {
+        ctx.accounts.migrate_ico_config(&ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn propose_owner(ctx: Context<ProposeOwner>, new_owner: Pubkey) -> Result<()> {
+        ctx.accounts.propose_owner(new_owner)?;
+        Ok(())
+    }
+
+    pub fn accept_owner(ctx: Context<AcceptOwner>) -> Result<()> {
+        ctx.accounts.accept_owner()?;
+        Ok(())
+    }
+
+    pub fn migrate_config(ctx: Context<MigrateConfig>) -> Result<()> {
+        ctx.accounts.migrate_config()?;
         Ok(())
     }
 }
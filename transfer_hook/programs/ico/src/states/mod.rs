@@ -1,13 +1,37 @@
 use anchor_lang::prelude::*;
+use solana_sha256_hasher::hashv;
+
+use crate::errors::ErrorCode;
+
+pub const MAX_SALE_TIERS: usize = 8;
 
 #[account]
 #[derive(InitSpace)]
 pub struct Config{
+    pub owner: Pubkey,
+    /// Protocol fee charged on ICO proceeds, in basis points.
+    pub fee:u64,
+    pub bump:u8,
+    /// Proposed by `propose_owner`, becomes the owner once it calls `accept_owner`.
+    pub pending_owner: Option<Pubkey>,
+}
+
+impl Config {
+    pub const FEE_DENOMINATOR: u64 = 10_000;
+}
+
+/// Layout of `Config` before two-step ownership transfers, kept for `migrate_config`.
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyConfig{
     pub owner: Pubkey,
     pub fee:u64,
     pub bump:u8,
 }
 
+impl LegacyConfig {
+    pub const LEN: usize = 32 + 8 + 1;
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct ICOVaultAccount{
@@ -22,19 +46,60 @@ pub struct ICOVaultAccount{
 pub struct ICOConfigAccount{
     pub creator: Pubkey,
     pub mint: Pubkey,
+    /// Minimum raise for the sale to succeed, in payment units (see `total_raised`).
     pub soft_cap: u64,
+    /// Maximum raise, in payment units (see `total_raised`).
     pub hard_cap: u64,
     pub start_time: i64,
     pub end_time: i64,
     pub token_vault: Pubkey,
+    /// Amount paid by buyers, in lamports or base units of `payment_mint`.
     pub total_raised: u64,
+    /// Base units of the sale mint sold so far, net of refunds.
+    pub tokens_sold: u64,
+    /// Part of `tokens_sold` bought before `migrate_ico_config`. Those buyers
+    /// paid the creator directly and have nothing to refund, so a failed sale
+    /// is settled once only they remain.
+    pub legacy_tokens_sold: u64,
+    /// Price per whole token, as a fixed-point value with `PRICE_DECIMALS`
+    /// decimals of the payment currency (lamports when paying in SOL).
     pub price_per_token: u64,
+    /// Smallest amount a single purchase may buy, 0 for no minimum.
+    pub min_purchase: u64,
+    /// Most a single wallet may buy across all purchases, 0 for no limit.
+    pub max_purchase_per_wallet: u64,
+    pub vesting: Option<VestingSchedule>,
+    pub allowlist: Option<AllowlistPhase>,
+    /// Set once the creator registers sale tiers; purchases are then priced per tier.
+    pub tiered: bool,
+    /// SPL / Token-2022 mint buyers pay with, or `None` to pay in SOL.
+    pub payment_mint: Option<Pubkey>,
+    /// Tokens sold under vesting that are still held in the vault for buyers.
+    pub total_unclaimed: u64,
     pub bump:u8,
 }
 
-#[account]
-#[derive(InitSpace)]
-pub struct ICOPurchaseAccount{
+impl ICOConfigAccount {
+    /// Decimals prices are quoted in, matching SOL's lamports.
+    pub const PRICE_DECIMALS: u8 = 9;
+
+    /// Converts a quote (base units bought times price per whole token) into
+    /// base units of the payment currency, rounding up in favour of the sale.
+    pub fn to_payment_units(quote: u128, mint_decimals: u8, payment_decimals: u8) -> Option<u64> {
+        let quote_decimals = mint_decimals as u32 + Self::PRICE_DECIMALS as u32;
+        let payment_decimals = payment_decimals as u32;
+        let amount = if payment_decimals >= quote_decimals {
+            quote.checked_mul(10u128.checked_pow(payment_decimals - quote_decimals)?)?
+        } else {
+            let scale = 10u128.checked_pow(quote_decimals - payment_decimals)?;
+            quote.div_ceil(scale)
+        };
+        u64::try_from(amount).ok()
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyICOConfigAccount{
     pub creator: Pubkey,
     pub mint: Pubkey,
     pub soft_cap: u64,
@@ -47,3 +112,195 @@ pub struct ICOPurchaseAccount{
     pub bump:u8,
 }
 
+impl LegacyICOConfigAccount {
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 8 + 8 + 32 + 8 + 8 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct VestingSchedule{
+    /// Share of each purchase unlocked when the sale ends, in basis points.
+    pub tge_unlock_bps: u16,
+    /// Seconds after `end_time` before linear vesting starts.
+    pub cliff_duration: i64,
+    /// Seconds over which the remainder unlocks linearly after the cliff.
+    pub vesting_duration: i64,
+}
+
+impl VestingSchedule {
+    pub const BPS_DENOMINATOR: u16 = 10_000;
+
+    pub fn is_valid(&self) -> bool {
+        self.tge_unlock_bps <= Self::BPS_DENOMINATOR
+            && self.cliff_duration >= 0
+            && self.vesting_duration >= 0
+    }
+
+    /// Amount of `total` unlocked `elapsed` seconds after the sale ended.
+    pub fn unlocked_amount(&self, total: u64, elapsed: i64) -> Option<u64> {
+        if elapsed < 0 {
+            return Some(0);
+        }
+
+        let tge_amount = (total as u128)
+            .checked_mul(self.tge_unlock_bps as u128)?
+            .checked_div(Self::BPS_DENOMINATOR as u128)? as u64;
+        if elapsed < self.cliff_duration {
+            return Some(tge_amount);
+        }
+
+        let vested_time = elapsed - self.cliff_duration;
+        if vested_time >= self.vesting_duration {
+            return Some(total);
+        }
+
+        let linear_amount = ((total - tge_amount) as u128)
+            .checked_mul(vested_time as u128)?
+            .checked_div(self.vesting_duration as u128)? as u64;
+        tge_amount.checked_add(linear_amount)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct AllowlistPhase{
+    /// Root of a sorted-pair SHA-256 Merkle tree over the allowlisted leaves.
+    pub merkle_root: [u8; 32],
+    /// Purchases before this time require a valid proof.
+    pub end_time: i64,
+}
+
+impl AllowlistPhase {
+    const LEAF_PREFIX: &'static [u8] = &[0];
+    const NODE_PREFIX: &'static [u8] = &[1];
+
+    /// Leaves are `sha256(0x00 || buyer)`, or `sha256(0x00 || buyer || allocation_le)`
+    /// when the allowlist assigns the buyer an allocation.
+    pub fn leaf(buyer: &Pubkey, allocation: Option<u64>) -> [u8; 32] {
+        match allocation {
+            Some(allocation) => hashv(&[Self::LEAF_PREFIX, buyer.as_ref(), &allocation.to_le_bytes()]),
+            None => hashv(&[Self::LEAF_PREFIX, buyer.as_ref()]),
+        }
+        .to_bytes()
+    }
+
+    pub fn verify(&self, buyer: &Pubkey, allocation: Option<u64>, proof: &[[u8; 32]]) -> bool {
+        let computed = proof.iter().fold(Self::leaf(buyer, allocation), |node, sibling| {
+            let (left, right) = if node <= *sibling { (node, *sibling) } else { (*sibling, node) };
+            hashv(&[Self::NODE_PREFIX, &left, &right]).to_bytes()
+        });
+        computed == self.merkle_root
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct SaleTier{
+    /// Price per whole token, in the same fixed-point units as `ICOConfigAccount::price_per_token`.
+    pub price_per_token: u64,
+    /// Tokens allocated to this tier.
+    pub cap: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub sold: u64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ICOTiersAccount{
+    pub mint: Pubkey,
+    #[max_len(MAX_SALE_TIERS)]
+    pub tiers: Vec<SaleTier>,
+    pub bump:u8,
+}
+
+impl ICOTiersAccount {
+    /// Fills `amount` from the active tier, spilling over into the following
+    /// tiers as each one sells out, and returns the total quote (see
+    /// `ICOConfigAccount::to_payment_units`).
+    ///
+    /// A tier is active once its start time has passed, or as soon as the tier
+    /// before it has sold out. Tiers that have ended are skipped.
+    pub fn fill(&mut self, amount: u64, current_time: i64) -> Result<u128> {
+        let mut remaining = amount;
+        let mut total_quote: u128 = 0;
+        let mut previous_sold_out = false;
+
+        for tier in self.tiers.iter_mut() {
+            if remaining == 0 {
+                break;
+            }
+
+            let available = tier.cap.checked_sub(tier.sold).ok_or(ErrorCode::Overflow)?;
+            if available == 0 {
+                previous_sold_out = true;
+                continue;
+            }
+            if current_time > tier.end_time {
+                previous_sold_out = false;
+                continue;
+            }
+            if current_time < tier.start_time && !previous_sold_out {
+                break;
+            }
+
+            let take = remaining.min(available);
+            let quote = (take as u128)
+                .checked_mul(tier.price_per_token as u128)
+                .ok_or(ErrorCode::Overflow)?;
+            total_quote = total_quote.checked_add(quote).ok_or(ErrorCode::Overflow)?;
+            tier.sold = tier.sold.checked_add(take).ok_or(ErrorCode::Overflow)?;
+            remaining -= take;
+            previous_sold_out = take == available;
+        }
+
+        require!(remaining == 0, ErrorCode::SaleTiersSoldOut);
+        Ok(total_quote)
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ICOEscrowAccount{
+    pub mint: Pubkey,
+    pub creator: Pubkey,
+    pub bump:u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ICOPurchaseAccount{
+    pub buyer: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub amount_paid: u64,
+    /// Tokens already delivered to the buyer.
+    pub claimed: u64,
+    pub bump:u8,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_payment_units_rounds_a_sub_unit_remainder_up() {
+        // 1 base unit of a 6-decimal mint at 0.000000001 SOL per token
+        assert_eq!(ICOConfigAccount::to_payment_units(1, 6, 9), Some(1));
+        // 3.5 lamports rounds up to 4
+        assert_eq!(ICOConfigAccount::to_payment_units(3_500_000, 6, 9), Some(4));
+        assert_eq!(ICOConfigAccount::to_payment_units(0, 6, 9), Some(0));
+    }
+
+    #[test]
+    fn to_payment_units_scales_up_when_payment_has_more_decimals() {
+        // 1 base unit of a 0-decimal mint at 1 price unit, paid in an 18-decimal token
+        assert_eq!(ICOConfigAccount::to_payment_units(1, 0, 18), Some(1_000_000_000));
+        assert_eq!(ICOConfigAccount::to_payment_units(7, 0, 9), Some(7));
+    }
+
+    #[test]
+    fn to_payment_units_returns_none_past_u64() {
+        assert_eq!(ICOConfigAccount::to_payment_units(u64::MAX as u128, 0, 9), Some(u64::MAX));
+        assert_eq!(ICOConfigAccount::to_payment_units(u64::MAX as u128 + 1, 0, 9), None);
+        assert_eq!(ICOConfigAccount::to_payment_units(u64::MAX as u128, 0, 10), None);
+        assert_eq!(ICOConfigAccount::to_payment_units(u128::MAX, 0, 255), None);
+    }
+}
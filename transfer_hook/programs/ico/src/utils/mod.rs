@@ -0,0 +1,111 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::system_program::{transfer, Transfer};
+use spl_token_2022::extension::{transfer_hook, StateWithExtensions};
+use spl_transfer_hook_interface::{get_extra_account_metas_address, onchain::add_extra_accounts_for_execute_cpi};
+
+use crate::errors::ErrorCode;
+
+/// `spl_token_2022::onchain::invoke_transfer_checked`, after checking that
+/// `remaining_accounts` wire up the mint's transfer hook, if it has one.
+#[allow(clippy::too_many_arguments)]
+pub fn invoke_transfer_checked<'info>(
+    token_program_id: &Pubkey,
+    source: AccountInfo<'info>,
+    mint: AccountInfo<'info>,
+    destination: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    amount: u64,
+    decimals: u8,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    check_transfer_hook_accounts(&source, &mint, &destination, &authority, remaining_accounts, amount)?;
+    spl_token_2022::onchain::invoke_transfer_checked(
+        token_program_id,
+        source,
+        mint,
+        destination,
+        authority,
+        remaining_accounts,
+        amount,
+        decimals,
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// Checks that `remaining_accounts` hold the mint's transfer hook program, its
+/// `ExtraAccountMetaList` and every extra account the list resolves for this
+/// transfer. Mints without a hook pass unchecked.
+pub fn check_transfer_hook_accounts<'info>(
+    source: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    remaining_accounts: &[AccountInfo<'info>],
+    amount: u64,
+) -> Result<()> {
+    if mint.owner != &spl_token_2022::ID {
+        return Ok(());
+    }
+    let hook_program_id = {
+        let data = mint.try_borrow_data()?;
+        let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+        transfer_hook::get_program_id(&state)
+    };
+    let Some(hook_program_id) = hook_program_id else {
+        return Ok(());
+    };
+
+    // Token-2022 skips the extra accounts when the list is missing, so require it up front
+    let meta_list_address = get_extra_account_metas_address(mint.key, &hook_program_id);
+    require!(
+        remaining_accounts
+            .iter()
+            .any(|account| account.key == &meta_list_address && account.owner == &hook_program_id),
+        ErrorCode::InvalidTransferHookAccounts
+    );
+
+    // Resolve the execute accounts the same way Token-2022 will, discarding the result
+    let mut instruction = Instruction::new_with_bytes(*mint.owner, &[], vec![]);
+    let mut account_infos = Vec::new();
+    add_extra_accounts_for_execute_cpi(
+        &mut instruction,
+        &mut account_infos,
+        &hook_program_id,
+        source.clone(),
+        mint.clone(),
+        destination.clone(),
+        authority.clone(),
+        amount,
+        remaining_accounts,
+    )
+    .map_err(|_| error!(ErrorCode::InvalidTransferHookAccounts))?;
+    Ok(())
+}
+
+/// Grows a legacy account to `new_len`, topping its rent up from `payer`, and
+/// writes `migrated` over it in the current layout.
+pub fn rewrite_account<'info, T: AccountSerialize>(
+    account: &AccountInfo<'info>,
+    migrated: &T,
+    new_len: usize,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let rent_shortfall = Rent::get()?
+        .minimum_balance(new_len)
+        .saturating_sub(account.lamports());
+    if rent_shortfall > 0 {
+        let accounts = Transfer {
+            from: payer.clone(),
+            to: account.clone(),
+        };
+        let ctx = CpiContext::new(system_program.clone(), accounts);
+        transfer(ctx, rent_shortfall)?;
+    }
+    account.resize(new_len)?;
+    migrated.try_serialize(&mut &mut account.try_borrow_mut_data()?[..])?;
+    Ok(())
+}
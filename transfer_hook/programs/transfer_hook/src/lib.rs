@@ -1,8 +1,14 @@
 use anchor_lang::{
     prelude::*,
+    solana_program::sysvar::instructions::{
+        self as sysvar_instructions, load_current_index_checked, load_instruction_at_checked,
+    },
     system_program::{transfer, create_account, CreateAccount, Transfer},
 };
-use anchor_spl::token_interface::{Mint, TokenAccount};
+use anchor_spl::{
+    token::Token,
+    token_interface::{transfer_checked, Mint, TokenAccount, TransferChecked},
+};
 use spl_tlv_account_resolution::{
     account::ExtraAccountMeta, seeds::Seed, state::ExtraAccountMetaList,
     solana_pubkey::Pubkey as SplPubkey,
@@ -18,10 +24,13 @@ declare_id!("AjNBZRCm6jsPjPRiZ3hbAitg9KEgCYqKmGm675Fpi6XU");
 
 pub const TREASURY_ADDRESS: Pubkey = pubkey!("HtGXcunbPUU54wMa9ZiXdMXvv1b5ppT7DeFLJWdtH7Lr");
 
+pub const TOKEN_METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
 #[program]
 pub mod transfer_hook {
     use super::*;
 
+    #[allow(clippy::too_many_arguments)]
     pub fn initialize_registry(
         ctx: Context<InitializeRegistry>,
         open_minute: Option<u16>,
@@ -29,8 +38,14 @@ pub mod transfer_hook {
         max_transfer_amount: u64,
         min_transfer_amount: u64,
         nft_mint_address: Pubkey,
+        nft_collection: Option<Pubkey>,
+        trading_starts_at: Option<i64>,
     ) -> Result<()> {
-        
+        // A launch start can only be scheduled here; a live token never closes again
+        if let Some(starts_at) = trading_starts_at {
+            require!(starts_at > Clock::get()?.unix_timestamp, HookError::InvalidTradingStart);
+        }
+
         let fee_lamports = 100_000_000;//0.1 SOL
         transfer(
             CpiContext::new(
@@ -45,47 +60,36 @@ pub mod transfer_hook {
 
         let config = &mut ctx.accounts.config;
         config.owner = ctx.accounts.payer.key();
+        config.pending_owner = None;
         config.mint = ctx.accounts.mint.key();
         
         config.whitelist_enabled = false;
+        config.destination_whitelist_enabled = false;
         config.trading_time_enabled = false;
         config.max_transfer_enabled = false;
         config.nft_gated = false;
+        config.blacklist_enabled = false;
+        config.volume_limit_enabled = false;
+        config.cooldown_enabled = false;
+        config.max_wallet_balance_enabled = false;
         
         config.open_minute = open_minute;
         config.close_minute = close_minute;
         config.max_transfer_amount = max_transfer_amount;
         config.min_transfer_amount = min_transfer_amount;
         config.nft_mint_address = nft_mint_address;
+        config.nft_collection = nft_collection;
+        config.volume_window = 0;
+        config.max_volume_per_window = 0;
+        config.cooldown_seconds = 0;
+        config.max_wallet_balance = 0;
+        config.trading_days = ConfigAccount::ALL_TRADING_DAYS;
+        config.utc_offset_minutes = 0;
+        config.trading_starts_at = trading_starts_at.unwrap_or(0);
+        config.halted = false;
+        config.timelock_seconds = 0;
 
-        let account_metas = vec![
-            // Index 5: The Config Account
-            ExtraAccountMeta::new_with_seeds(
-                &[Seed::Literal { bytes: b"config".to_vec() }, Seed::AccountKey { index: 1 }],
-                false, false,
-            ).map_err(|_| error!(HookError::MetaListError))?,
-            // Index 6: The Whitelist Marker 
-            ExtraAccountMeta::new_with_seeds(
-                &[Seed::Literal { bytes: b"whitelist".to_vec() }, Seed::AccountKey { index: 1 }, Seed::AccountKey { index: 3 }],
-                false, false,
-            ).map_err(|_| error!(HookError::MetaListError))?,
-            // Index 7: NFT Mint Account 
-            ExtraAccountMeta::new_with_pubkey(&SplPubkey::new_from_array(nft_mint_address.to_bytes()), false, false).map_err(|_| error!(HookError::MetaListError))?,
-            // Index 8: SPL Token Program 
-            ExtraAccountMeta::new_with_pubkey(&SplPubkey::new_from_array(anchor_spl::token::ID.to_bytes()), false, false).map_err(|_| error!(HookError::MetaListError))?,
-            // Index 9: Associated Token Program 
-            ExtraAccountMeta::new_with_pubkey(&SplPubkey::new_from_array(anchor_spl::associated_token::ID.to_bytes()), false, false).map_err(|_| error!(HookError::MetaListError))?,
-            // Index 10: Senders Token Account
-            ExtraAccountMeta::new_external_pda_with_seeds(
-                9, // ATA Program index
-                &[
-                    Seed::AccountKey { index: 3 }, // Source Owner Wallet
-                    Seed::AccountKey { index: 8 }, // SPL Token Program
-                    Seed::AccountKey { index: 7 }, // NFT Mint
-                ],
-                false, false,
-            ).map_err(|_| error!(HookError::MetaListError))?,
-        ];
+        let account_metas = build_extra_account_metas(config)?;
 
         let accounts_size = ExtraAccountMetaList::size_of(account_metas.len()).map_err(|_| error!(HookError::MetaListError))? as u64;
         let lamports = Rent::get()?.minimum_balance(accounts_size as usize);
@@ -119,18 +123,150 @@ pub mod transfer_hook {
         Ok(())
     }
 
+    /// Rebuilds the meta list from the current config, e.g. after `edit_config`
+    /// changes `nft_mint_address`. Anyone may call it.
+    pub fn update_extra_account_meta_list(ctx: Context<UpdateExtraAccountMetaList>) -> Result<()> {
+        rebuild_extra_account_meta_list(
+            &ctx.accounts.config,
+            &ctx.accounts.extra_account_meta_list,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+        )
+    }
+
+    /// Upgrades a config created before the blacklist and later features
+    /// to the current layout, then rebuilds the meta list so it carries
+    /// every extra account `execute` now expects. Only the owner may call it.
+    pub fn migrate_config(ctx: Context<MigrateConfig>) -> Result<()> {
+        let config_info = ctx.accounts.config.to_account_info();
+        let legacy = {
+            let data = config_info.try_borrow_data()?;
+            require!(
+                config_info.owner == ctx.program_id
+                    && data.len() == 8 + LegacyConfigAccount::LEN
+                    && data.starts_with(ConfigAccount::DISCRIMINATOR),
+                HookError::NotLegacyConfig
+            );
+            LegacyConfigAccount::deserialize(&mut &data[8..])?
+        };
+        require_keys_eq!(legacy.owner, ctx.accounts.owner.key(), HookError::Unauthorized);
+
+        let config = ConfigAccount {
+            owner: legacy.owner,
+            pending_owner: None,
+            mint: legacy.mint,
+            nft_mint_address: legacy.nft_mint_address,
+            nft_collection: None,
+            whitelist_enabled: legacy.whitelist_enabled,
+            destination_whitelist_enabled: false,
+            trading_time_enabled: legacy.trading_time_enabled,
+            max_transfer_enabled: legacy.max_transfer_enabled,
+            nft_gated: legacy.nft_gated,
+            blacklist_enabled: false,
+            volume_limit_enabled: false,
+            cooldown_enabled: false,
+            max_wallet_balance_enabled: false,
+            open_minute: legacy.open_minute,
+            close_minute: legacy.close_minute,
+            max_transfer_amount: legacy.max_transfer_amount,
+            min_transfer_amount: legacy.min_transfer_amount,
+            volume_window: 0,
+            max_volume_per_window: 0,
+            cooldown_seconds: 0,
+            max_wallet_balance: 0,
+            trading_days: ConfigAccount::ALL_TRADING_DAYS,
+            utc_offset_minutes: 0,
+            trading_starts_at: 0,
+            halted: false,
+            timelock_seconds: 0,
+        };
+
+        resize_with_rent(
+            &config_info,
+            8 + ConfigAccount::INIT_SPACE,
+            &ctx.accounts.owner,
+            &ctx.accounts.system_program,
+        )?;
+        config.try_serialize(&mut &mut config_info.try_borrow_mut_data()?[..])?;
+
+        rebuild_extra_account_meta_list(
+            &config,
+            &ctx.accounts.extra_account_meta_list,
+            &ctx.accounts.owner,
+            &ctx.accounts.system_program,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn update_flags(
         ctx: Context<UpdateConfig>,
         whitelist_enabled: bool,
         trading_time_enabled: bool,
         max_transfer_enabled: bool,
         nft_gated: bool,
+        blacklist_enabled: bool,
+        destination_whitelist_enabled: bool,
+        volume_limit_enabled: bool,
+        cooldown_enabled: bool,
+        max_wallet_balance_enabled: bool,
     ) -> Result<()> {
+        let flags = ConfigFlags {
+            whitelist_enabled,
+            trading_time_enabled,
+            max_transfer_enabled,
+            nft_gated,
+            blacklist_enabled,
+            destination_whitelist_enabled,
+            volume_limit_enabled,
+            cooldown_enabled,
+            max_wallet_balance_enabled,
+        };
+        apply_or_queue(
+            &mut ctx.accounts.config,
+            ctx.accounts.pending_change.as_mut(),
+            ctx.accounts.payer.as_ref(),
+            ctx.bumps.pending_change,
+            None,
+            ConfigChange::Flags(flags),
+        )
+    }
+
+    pub fn halt(ctx: Context<PauseTrading>) -> Result<()> {
+        ctx.accounts.config.halted = true;
+        Ok(())
+    }
+
+    pub fn resume(ctx: Context<PauseTrading>) -> Result<()> {
+        ctx.accounts.config.halted = false;
+        Ok(())
+    }
+
+    pub fn propose_owner(ctx: Context<ManageOwnership>, new_owner: Pubkey) -> Result<()> {
+        ctx.accounts.config.pending_owner = Some(new_owner);
+        Ok(())
+    }
+
+    pub fn accept_owner(ctx: Context<AcceptOwner>) -> Result<()> {
         let config = &mut ctx.accounts.config;
-        config.whitelist_enabled = whitelist_enabled;
-        config.trading_time_enabled = trading_time_enabled;
-        config.max_transfer_enabled = max_transfer_enabled;
-        config.nft_gated = nft_gated;
+        config.owner = ctx.accounts.new_owner.key();
+        config.pending_owner = None;
+        Ok(())
+    }
+
+    /// Hands the config to the default pubkey, which nobody can sign for, so
+    /// the configuration can never change again. Granted roles lapse with it.
+    pub fn renounce_ownership(ctx: Context<RenounceOwnership>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.owner = Pubkey::default();
+        config.pending_owner = None;
+        Ok(())
+    }
+
+    pub fn grant_role(_ctx: Context<GrantRole>, _role: Role) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn revoke_role(_ctx: Context<RevokeRole>, _role: Role) -> Result<()> {
         Ok(())
     }
 
@@ -142,6 +278,106 @@ pub mod transfer_hook {
         Ok(())
     }
 
+    pub fn add_to_blacklist(_ctx: Context<AddToBlacklist>) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn remove_from_blacklist(_ctx: Context<RemoveFromBlacklist>) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn add_balance_exemption(_ctx: Context<AddBalanceExemption>) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn remove_balance_exemption(_ctx: Context<RemoveBalanceExemption>) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn add_exemption(_ctx: Context<AddExemption>) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn remove_exemption(_ctx: Context<RemoveExemption>) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn initialize_calendar(ctx: Context<InitializeCalendar>) -> Result<()> {
+        let calendar = &mut ctx.accounts.trading_calendar;
+        calendar.mint = ctx.accounts.mint.key();
+        calendar.closed_ranges = Vec::new();
+        calendar.bump = ctx.bumps.trading_calendar;
+        Ok(())
+    }
+
+    pub fn add_closed_range(ctx: Context<UpdateCalendar>, start_time: i64, end_time: i64) -> Result<()> {
+        require!(start_time < end_time, HookError::InvalidClosedRange);
+
+        let calendar = &mut ctx.accounts.trading_calendar;
+        require!(calendar.closed_ranges.len() < MAX_CLOSED_RANGES, HookError::CalendarFull);
+        calendar.closed_ranges.push(ClosedRange { start_time, end_time });
+        Ok(())
+    }
+
+    pub fn remove_closed_range(ctx: Context<UpdateCalendar>, start_time: i64, end_time: i64) -> Result<()> {
+        let calendar = &mut ctx.accounts.trading_calendar;
+        let index = calendar
+            .closed_ranges
+            .iter()
+            .position(|range| range.start_time == start_time && range.end_time == end_time)
+            .ok_or(HookError::ClosedRangeNotFound)?;
+        calendar.closed_ranges.remove(index);
+        Ok(())
+    }
+
+    pub fn initialize_trading_sessions(
+        ctx: Context<InitializeTradingSessions>,
+        sessions: Vec<TradingSession>,
+    ) -> Result<()> {
+        validate_sessions(&sessions)?;
+
+        let trading_sessions = &mut ctx.accounts.trading_sessions;
+        trading_sessions.mint = ctx.accounts.mint.key();
+        trading_sessions.sessions = sessions;
+        trading_sessions.bump = ctx.bumps.trading_sessions;
+        Ok(())
+    }
+
+    pub fn update_trading_sessions(
+        ctx: Context<UpdateTradingSessions>,
+        sessions: Vec<TradingSession>,
+    ) -> Result<()> {
+        validate_sessions(&sessions)?;
+        ctx.accounts.trading_sessions.sessions = sessions;
+        Ok(())
+    }
+
+    pub fn register_nft_pass(ctx: Context<RegisterNftPass>) -> Result<()> {
+        let collection = ctx.accounts.config.nft_collection.ok_or(HookError::NotInCollection)?;
+        verify_collection_metadata(&collection, &ctx.accounts.nft_mint.key(), &ctx.accounts.nft_metadata)?;
+
+        let accounts = TransferChecked {
+            from: ctx.accounts.holder_nft_account.to_account_info(),
+            mint: ctx.accounts.nft_mint.to_account_info(),
+            to: ctx.accounts.nft_pass.to_account_info(),
+            authority: ctx.accounts.holder.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), accounts);
+        transfer_checked(cpi_ctx, 1, ctx.accounts.nft_mint.decimals)
+    }
+
+    pub fn initialize_wallet_state(ctx: Context<InitializeWalletState>) -> Result<()> {
+        let wallet_state = &mut ctx.accounts.wallet_state;
+        wallet_state.owner = ctx.accounts.wallet.key();
+        wallet_state.mint = ctx.accounts.mint.key();
+        wallet_state.window_start = 0;
+        wallet_state.window_volume = 0;
+        wallet_state.last_transfer_at = 0;
+        wallet_state.bump = ctx.bumps.wallet_state;
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn edit_config(
         ctx: Context<EditConfig>,        
         open_minute: Option<u16>,
@@ -149,25 +385,56 @@ pub mod transfer_hook {
         max_transfer_amount: Option<u64>,
         min_transfer_amount: Option<u64>,
         nft_mint_address: Option<Pubkey>, 
+        volume_window: Option<i64>,
+        max_volume_per_window: Option<u64>,
+        cooldown_seconds: Option<i64>,
+        max_wallet_balance: Option<u64>,
+        trading_days: Option<u8>,
+        utc_offset_minutes: Option<i16>,
+        trading_starts_at: Option<i64>,
+        timelock_seconds: Option<i64>,
     ) -> Result<()> {
-        let config = &mut ctx.accounts.config;
-
-        if let (Some(open), Some(close)) = (open_minute, close_minute) {
-            config.open_minute = Some(open);
-            config.close_minute = Some(close);
-        }
-
-        if let (Some(max), Some(min)) = (max_transfer_amount, min_transfer_amount) {
-            require!(max != 0 && min != 0, HookError::InvalidTransferAmount);
-            config.max_transfer_amount = max;
-            config.min_transfer_amount = min;
-        }
+        let edit = ConfigEdit {
+            open_minute,
+            close_minute,
+            max_transfer_amount,
+            min_transfer_amount,
+            nft_mint_address,
+            volume_window,
+            max_volume_per_window,
+            cooldown_seconds,
+            max_wallet_balance,
+            trading_days,
+            utc_offset_minutes,
+            trading_starts_at,
+            timelock_seconds,
+        };
+        apply_or_queue(
+            &mut ctx.accounts.config,
+            ctx.accounts.pending_change.as_mut(),
+            ctx.accounts.payer.as_ref(),
+            ctx.bumps.pending_change,
+            ctx.accounts.instructions_sysvar.as_ref(),
+            ConfigChange::Edit(edit),
+        )
+    }
 
-        if let Some(mint_address) = nft_mint_address {
-            require!(mint_address != Pubkey::default(), HookError::InvalidPubkey);
-            config.nft_mint_address = mint_address;
-        }
+    /// Commits a queued change once its delay has passed. Anyone may call it.
+    pub fn apply_pending(ctx: Context<ApplyPending>) -> Result<()> {
+        let pending_change = &ctx.accounts.pending_change;
+        require!(
+            Clock::get()?.unix_timestamp >= pending_change.effective_at,
+            HookError::TimelockNotElapsed
+        );
+        require_meta_list_rebuild(
+            &ctx.accounts.config,
+            &pending_change.change,
+            ctx.accounts.instructions_sysvar.as_ref(),
+        )?;
+        ctx.accounts.config.apply_change(&pending_change.change)
+    }
 
+    pub fn cancel_pending(_ctx: Context<CancelPending>) -> Result<()> {
         Ok(())
     }
    
@@ -175,24 +442,77 @@ pub mod transfer_hook {
     pub fn execute(ctx: Context<ExecuteTransfer>, amount: u64) -> Result<()> {
         let config = &ctx.accounts.config;
 
+        // A halt freezes every transfer, exempt ones included
+        require!(!config.halted, HookError::TradingHalted);
+
+        // Sanctions apply even when the other side is exempt, so a blacklisted
+        // wallet cannot sell into a pool
+        if config.blacklist_enabled {
+            require!(
+                !is_marker_initialized(&ctx.accounts.source_blacklist_marker, ctx.program_id),
+                HookError::SenderBlacklisted
+            );
+            require!(
+                !is_marker_initialized(&ctx.accounts.destination_blacklist_marker, ctx.program_id),
+                HookError::RecipientBlacklisted
+            );
+        }
+
+        // Exempt wallets and token accounts (ICO vaults, AMM pools, ...) skip every other rule
+        let is_exempt = [
+            &ctx.accounts.source_owner_exempt_marker,
+            &ctx.accounts.source_token_exempt_marker,
+            &ctx.accounts.destination_owner_exempt_marker,
+            &ctx.accounts.destination_token_exempt_marker,
+        ]
+        .into_iter()
+        .any(|marker| is_marker_initialized(marker, ctx.program_id));
+        if is_exempt {
+            return Ok(());
+        }
+
+        require!(
+            Clock::get()?.unix_timestamp >= config.trading_starts_at,
+            HookError::TradingNotStarted
+        );
+
         if config.nft_gated {
-            let nft_ata = &ctx.accounts.nft_token_account;
-            
-            require!(!nft_ata.data_is_empty(), HookError::MissingNftAccount);
-            require!(nft_ata.data_len() >= 72, HookError::MissingNftAccount);
+            if config.nft_collection.is_some() {
+                verify_nft_pass(
+                    &ctx.accounts.mint.key(),
+                    ctx.accounts.owner.key,
+                    ctx.remaining_accounts,
+                    ctx.program_id,
+                )?;
+            } else {
+                let nft_ata = &ctx.accounts.nft_token_account;
 
-            let data = nft_ata.try_borrow_data()?;
-            let mut amount_bytes = [0u8; 8];
-            amount_bytes.copy_from_slice(&data[64..72]);
-            let nft_balance = u64::from_le_bytes(amount_bytes);
+                require!(!nft_ata.data_is_empty(), HookError::MissingNftAccount);
+                require!(nft_ata.data_len() >= 72, HookError::MissingNftAccount);
 
-            require!(nft_balance > 0, HookError::MissingNftAccount);
+                let data = nft_ata.try_borrow_data()?;
+                let mut amount_bytes = [0u8; 8];
+                amount_bytes.copy_from_slice(&data[64..72]);
+                let nft_balance = u64::from_le_bytes(amount_bytes);
+
+                require!(nft_balance > 0, HookError::MissingNftAccount);
+            }
         }
 
         if config.trading_time_enabled {
-            if let (Some(open), Some(close)) = (config.open_minute, config.close_minute) {
-                validate_trading_hours(open, close)?;
-            }
+            let calendar_info = &ctx.accounts.trading_calendar;
+            let calendar = if is_marker_initialized(calendar_info, ctx.program_id) {
+                Some(TradingCalendar::try_deserialize(&mut &calendar_info.try_borrow_data()?[..])?)
+            } else {
+                None
+            };
+            let sessions_info = &ctx.accounts.trading_sessions;
+            let sessions = if is_marker_initialized(sessions_info, ctx.program_id) {
+                Some(TradingSessions::try_deserialize(&mut &sessions_info.try_borrow_data()?[..])?)
+            } else {
+                None
+            };
+            validate_trading_hours(config, calendar.as_ref(), sessions.as_ref())?;
         }
 
         if config.max_transfer_enabled {
@@ -200,34 +520,427 @@ pub mod transfer_hook {
             require!(amount >= config.min_transfer_amount, HookError::BelowMinTransfer);
         }
         if config.whitelist_enabled {
-            let marker_account = &ctx.accounts.whitelist_marker;
-            
-            let is_initialized = marker_account.lamports() > 0 && marker_account.owner == ctx.program_id;
+            let is_initialized = is_marker_initialized(&ctx.accounts.whitelist_marker, ctx.program_id);
             require!(is_initialized, HookError::NotWhitelisted);
         }
 
+        if config.destination_whitelist_enabled {
+            let is_initialized = is_marker_initialized(&ctx.accounts.destination_whitelist_marker, ctx.program_id);
+            require!(is_initialized, HookError::RecipientNotWhitelisted);
+        }
+
+        if config.volume_limit_enabled || config.cooldown_enabled {
+            let wallet_state_info = &ctx.accounts.wallet_state;
+            require!(
+                is_marker_initialized(wallet_state_info, ctx.program_id),
+                HookError::WalletStateNotInitialized
+            );
+
+            let mut wallet_state = WalletState::try_deserialize(&mut &wallet_state_info.try_borrow_data()?[..])?;
+            let now = Clock::get()?.unix_timestamp;
+
+            if config.cooldown_enabled {
+                require!(
+                    wallet_state.last_transfer_at == 0
+                        || now.saturating_sub(wallet_state.last_transfer_at) >= config.cooldown_seconds,
+                    HookError::TransferCooldownActive
+                );
+            }
+            if config.volume_limit_enabled {
+                wallet_state.record_volume(amount, now, config.volume_window, config.max_volume_per_window)?;
+            }
+
+            wallet_state.last_transfer_at = now;
+            wallet_state.try_serialize(&mut &mut wallet_state_info.try_borrow_mut_data()?[..])?;
+        }
+
+        // The hook runs after the balances have moved, so this is the post-transfer balance
+        if config.max_wallet_balance_enabled
+            && !is_marker_initialized(&ctx.accounts.balance_exempt_marker, ctx.program_id)
+        {
+            require!(
+                ctx.accounts.destination_token.amount <= config.max_wallet_balance,
+                HookError::ExceedsMaxWalletBalance
+            );
+        }
+
         Ok(())
     }
 }
 
-pub fn validate_trading_hours(open_minute: u16, close_minute: u16) -> Result<()> {
+/// Rewrites the meta list from `config`, growing the account and topping up
+/// its rent from `payer` first when the list got longer.
+fn rebuild_extra_account_meta_list<'info>(
+    config: &ConfigAccount,
+    meta_list: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let account_metas = build_extra_account_metas(config)?;
+    let accounts_size = ExtraAccountMetaList::size_of(account_metas.len()).map_err(|_| error!(HookError::MetaListError))?;
+    resize_with_rent(meta_list, accounts_size, payer, system_program)?;
+
+    ExtraAccountMetaList::update::<ExecuteInstruction>(
+        &mut meta_list.try_borrow_mut_data()?,
+        &account_metas,
+    ).map_err(|_| error!(HookError::MetaListError))?;
+    Ok(())
+}
+
+/// Resizes a program-owned account, with `payer` covering any extra rent.
+fn resize_with_rent<'info>(
+    account: &AccountInfo<'info>,
+    new_len: usize,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let rent_shortfall = Rent::get()?
+        .minimum_balance(new_len)
+        .saturating_sub(account.lamports());
+    if rent_shortfall > 0 {
+        transfer(
+            CpiContext::new(
+                system_program.clone(),
+                Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            rent_shortfall,
+        )?;
+    }
+    account.resize(new_len)?;
+    Ok(())
+}
+
+/// Extra accounts `execute` needs for the given config. The NFT mint is
+/// stored in the list itself, so it has to be rebuilt whenever it changes.
+pub fn build_extra_account_metas(config: &ConfigAccount) -> Result<Vec<ExtraAccountMeta>> {
+    let mut account_metas = vec![
+        // Index 5: The Config Account
+        ExtraAccountMeta::new_with_seeds(
+            &[Seed::Literal { bytes: b"config".to_vec() }, Seed::AccountKey { index: 1 }],
+            false, false,
+        ).map_err(|_| error!(HookError::MetaListError))?,
+        // Index 6: The Whitelist Marker 
+        ExtraAccountMeta::new_with_seeds(
+            &[Seed::Literal { bytes: b"whitelist".to_vec() }, Seed::AccountKey { index: 1 }, Seed::AccountKey { index: 3 }],
+            false, false,
+        ).map_err(|_| error!(HookError::MetaListError))?,
+        // Index 7: NFT Mint Account 
+        ExtraAccountMeta::new_with_pubkey(&SplPubkey::new_from_array(config.nft_mint_address.to_bytes()), false, false).map_err(|_| error!(HookError::MetaListError))?,
+        // Index 8: SPL Token Program 
+        ExtraAccountMeta::new_with_pubkey(&SplPubkey::new_from_array(anchor_spl::token::ID.to_bytes()), false, false).map_err(|_| error!(HookError::MetaListError))?,
+        // Index 9: Associated Token Program 
+        ExtraAccountMeta::new_with_pubkey(&SplPubkey::new_from_array(anchor_spl::associated_token::ID.to_bytes()), false, false).map_err(|_| error!(HookError::MetaListError))?,
+        // Index 10: Senders Token Account
+        ExtraAccountMeta::new_external_pda_with_seeds(
+            9, // ATA Program index
+            &[
+                Seed::AccountKey { index: 3 }, // Source Owner Wallet
+                Seed::AccountKey { index: 8 }, // SPL Token Program
+                Seed::AccountKey { index: 7 }, // NFT Mint
+            ],
+            false, false,
+        ).map_err(|_| error!(HookError::MetaListError))?,
+        // Index 11: Source Owner Blacklist Marker
+        ExtraAccountMeta::new_with_seeds(
+            &[Seed::Literal { bytes: b"blacklist".to_vec() }, Seed::AccountKey { index: 1 }, Seed::AccountKey { index: 3 }],
+            false, false,
+        ).map_err(|_| error!(HookError::MetaListError))?,
+        // Index 12: Destination Owner Blacklist Marker (owner read from the destination token account)
+        ExtraAccountMeta::new_with_seeds(
+            &[
+                Seed::Literal { bytes: b"blacklist".to_vec() },
+                Seed::AccountKey { index: 1 },
+                Seed::AccountData { account_index: 2, data_index: 32, length: 32 },
+            ],
+            false, false,
+        ).map_err(|_| error!(HookError::MetaListError))?,
+        // Index 13: Destination Owner Whitelist Marker
+        ExtraAccountMeta::new_with_seeds(
+            &[
+                Seed::Literal { bytes: b"whitelist".to_vec() },
+                Seed::AccountKey { index: 1 },
+                Seed::AccountData { account_index: 2, data_index: 32, length: 32 },
+            ],
+            false, false,
+        ).map_err(|_| error!(HookError::MetaListError))?,
+        // Index 14: Source Owner Wallet State (writable, updated by the per-wallet rules)
+        ExtraAccountMeta::new_with_seeds(
+            &[Seed::Literal { bytes: b"wallet-state".to_vec() }, Seed::AccountKey { index: 1 }, Seed::AccountKey { index: 3 }],
+            false, true,
+        ).map_err(|_| error!(HookError::MetaListError))?,
+        // Index 15: Destination Owner Balance Exemption Marker
+        ExtraAccountMeta::new_with_seeds(
+            &[
+                Seed::Literal { bytes: b"balance-exempt".to_vec() },
+                Seed::AccountKey { index: 1 },
+                Seed::AccountData { account_index: 2, data_index: 32, length: 32 },
+            ],
+            false, false,
+        ).map_err(|_| error!(HookError::MetaListError))?,
+        // Index 16: Source Owner Exemption Marker
+        ExtraAccountMeta::new_with_seeds(
+            &[Seed::Literal { bytes: b"exempt".to_vec() }, Seed::AccountKey { index: 1 }, Seed::AccountKey { index: 3 }],
+            false, false,
+        ).map_err(|_| error!(HookError::MetaListError))?,
+        // Index 17: Source Token Account Exemption Marker
+        ExtraAccountMeta::new_with_seeds(
+            &[Seed::Literal { bytes: b"exempt".to_vec() }, Seed::AccountKey { index: 1 }, Seed::AccountKey { index: 0 }],
+            false, false,
+        ).map_err(|_| error!(HookError::MetaListError))?,
+        // Index 18: Destination Owner Exemption Marker
+        ExtraAccountMeta::new_with_seeds(
+            &[
+                Seed::Literal { bytes: b"exempt".to_vec() },
+                Seed::AccountKey { index: 1 },
+                Seed::AccountData { account_index: 2, data_index: 32, length: 32 },
+            ],
+            false, false,
+        ).map_err(|_| error!(HookError::MetaListError))?,
+        // Index 19: Destination Token Account Exemption Marker
+        ExtraAccountMeta::new_with_seeds(
+            &[Seed::Literal { bytes: b"exempt".to_vec() }, Seed::AccountKey { index: 1 }, Seed::AccountKey { index: 2 }],
+            false, false,
+        ).map_err(|_| error!(HookError::MetaListError))?,
+        // Index 20: Trading Calendar
+        ExtraAccountMeta::new_with_seeds(
+            &[Seed::Literal { bytes: b"calendar".to_vec() }, Seed::AccountKey { index: 1 }],
+            false, false,
+        ).map_err(|_| error!(HookError::MetaListError))?,
+        // Index 21: Trading Sessions
+        ExtraAccountMeta::new_with_seeds(
+            &[Seed::Literal { bytes: b"sessions".to_vec() }, Seed::AccountKey { index: 1 }],
+            false, false,
+        ).map_err(|_| error!(HookError::MetaListError))?,
+    ];
+
+    // Collection gating only needs the sender's pass, which is derived from
+    // seeds alone so resolution never depends on the pass existing
+    if config.nft_collection.is_some() {
+        account_metas.push(
+            // Index 22: Sender's NFT Pass
+            ExtraAccountMeta::new_with_seeds(
+                &[Seed::Literal { bytes: b"nft-pass".to_vec() }, Seed::AccountKey { index: 1 }, Seed::AccountKey { index: 3 }],
+                false, false,
+            ).map_err(|_| error!(HookError::MetaListError))?,
+        );
+    }
+
+    Ok(account_metas)
+}
+
+/// Changing `nft_mint_address` leaves the meta list pointing at the old NFT,
+/// so the change is only allowed when `update_extra_account_meta_list` for
+/// this mint follows later in the same transaction.
+fn require_meta_list_rebuild(
+    config: &ConfigAccount,
+    change: &ConfigChange,
+    instructions_sysvar: Option<&UncheckedAccount>,
+) -> Result<()> {
+    let ConfigChange::Edit(ConfigEdit { nft_mint_address: Some(nft_mint_address), .. }) = change else {
+        return Ok(());
+    };
+    if *nft_mint_address == config.nft_mint_address {
+        return Ok(());
+    }
+
+    let instructions_sysvar = instructions_sysvar.ok_or(HookError::MetaListRebuildRequired)?;
+    let (meta_list, _) = Pubkey::find_program_address(&[b"extra-account-metas", config.mint.as_ref()], &crate::ID);
+    let mut index = load_current_index_checked(instructions_sysvar)? as usize + 1;
+    while let Ok(ix) = load_instruction_at_checked(index, instructions_sysvar) {
+        if ix.program_id == crate::ID
+            && ix.data.starts_with(instruction::UpdateExtraAccountMetaList::DISCRIMINATOR)
+            && ix.accounts.iter().any(|account| account.pubkey == meta_list)
+        {
+            return Ok(());
+        }
+        index += 1;
+    }
+    err!(HookError::MetaListRebuildRequired)
+}
+
+/// Applies `change` right away, or queues it in `pending_change` when the
+/// config has a timelock. Queued changes are validated up front as well.
+fn apply_or_queue(
+    config: &mut ConfigAccount,
+    pending_change: Option<&mut Account<PendingConfigChange>>,
+    payer: Option<&Signer>,
+    bump: Option<u8>,
+    instructions_sysvar: Option<&UncheckedAccount>,
+    change: ConfigChange,
+) -> Result<()> {
+    if config.timelock_seconds == 0 {
+        require!(pending_change.is_none(), HookError::InvalidPendingChange);
+        require_meta_list_rebuild(config, &change, instructions_sysvar)?;
+        return config.apply_change(&change);
+    }
+
+    let (Some(pending_change), Some(payer), Some(bump)) = (pending_change, payer, bump) else {
+        return err!(HookError::InvalidPendingChange);
+    };
+    config.clone().apply_change(&change)?;
+
+    pending_change.set_inner(PendingConfigChange {
+        mint: config.mint,
+        payer: payer.key(),
+        change,
+        effective_at: Clock::get()?
+            .unix_timestamp
+            .checked_add(config.timelock_seconds)
+            .ok_or(HookError::InvalidTimelock)?,
+        bump,
+    });
+    Ok(())
+}
+
+/// Marker PDAs carry no data; they count as set once created by this program.
+pub fn is_marker_initialized(marker_account: &AccountInfo, program_id: &Pubkey) -> bool {
+    marker_account.lamports() > 0 && marker_account.owner == program_id
+}
+
+/// Checks that `owner` keeps a collection NFT in their pass, the token
+/// account `register_nft_pass` creates at index 22 of the extra accounts.
+/// Only this program can create an account at the pass address, and a token
+/// account's mint never changes, so the collection was checked at registration.
+pub fn verify_nft_pass(
+    mint: &Pubkey,
+    owner: &Pubkey,
+    accounts: &[AccountInfo],
+    program_id: &Pubkey,
+) -> Result<()> {
+    let nft_pass = accounts.first().ok_or(HookError::InvalidNftPass)?;
+    let (pass_address, _) = Pubkey::find_program_address(&[b"nft-pass", mint.as_ref(), owner.as_ref()], program_id);
+    require_keys_eq!(nft_pass.key(), pass_address, HookError::InvalidNftPass);
+
+    // Token account layout: mint (0..32), owner (32..64), amount (64..72)
+    require_keys_eq!(*nft_pass.owner, anchor_spl::token::ID, HookError::MissingNftAccount);
+    let data = nft_pass.try_borrow_data()?;
+    require!(data.len() >= 72, HookError::MissingNftAccount);
+    require!(data[32..64] == owner.to_bytes(), HookError::MissingNftAccount);
+    let mut amount_bytes = [0u8; 8];
+    amount_bytes.copy_from_slice(&data[64..72]);
+    require!(u64::from_le_bytes(amount_bytes) > 0, HookError::MissingNftAccount);
+    Ok(())
+}
+
+/// Checks that `nft_metadata` is the Metaplex metadata of `nft_mint` and lists
+/// `collection` as its verified collection.
+pub fn verify_collection_metadata(collection: &Pubkey, nft_mint: &Pubkey, nft_metadata: &AccountInfo) -> Result<()> {
+    require_keys_eq!(*nft_metadata.owner, TOKEN_METADATA_PROGRAM_ID, HookError::NotInCollection);
+    let (metadata_address, _) = Pubkey::find_program_address(
+        &[b"metadata", TOKEN_METADATA_PROGRAM_ID.as_ref(), nft_mint.as_ref()],
+        &TOKEN_METADATA_PROGRAM_ID,
+    );
+    require_keys_eq!(nft_metadata.key(), metadata_address, HookError::NotInCollection);
+    let verified_collection = verified_collection(&nft_metadata.try_borrow_data()?)
+        .ok_or(HookError::NotInCollection)?;
+    require_keys_eq!(verified_collection, *collection, HookError::NotInCollection);
+    Ok(())
+}
+
+/// Reads the collection of a Metaplex metadata account, if it is verified.
+fn verified_collection(data: &[u8]) -> Option<Pubkey> {
+    fn read_u32(data: &[u8], offset: usize) -> Option<usize> {
+        Some(u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?) as usize)
+    }
+
+    // key, update_authority, mint
+    let mut offset = 1 + 32 + 32;
+    // name, symbol, uri
+    for _ in 0..3 {
+        offset += 4 + read_u32(data, offset)?;
+    }
+    // seller_fee_basis_points
+    offset += 2;
+    // creators: Option<Vec<Creator>>, 34 bytes per creator
+    if *data.get(offset)? == 1 {
+        offset += 4 + read_u32(data, offset + 1)? * 34;
+    }
+    offset += 1;
+    // primary_sale_happened, is_mutable
+    offset += 2;
+    // edition_nonce, token_standard: Option<u8>
+    for _ in 0..2 {
+        offset += if *data.get(offset)? == 1 { 2 } else { 1 };
+    }
+    // collection: Option<Collection { verified, key }>
+    if *data.get(offset)? != 1 || *data.get(offset + 1)? != 1 {
+        return None;
+    }
+    Pubkey::try_from(data.get(offset + 2..offset + 34)?).ok()
+}
+
+/// Checks the calendar, trading days and hours. When trading sessions are
+/// configured they replace the single `open_minute`/`close_minute` window.
+pub fn validate_trading_hours(
+    config: &ConfigAccount,
+    calendar: Option<&TradingCalendar>,
+    sessions: Option<&TradingSessions>,
+) -> Result<()> {
     let clock = Clock::get()?;
     let current_timestamp = clock.unix_timestamp; 
 
+    if let Some(calendar) = calendar {
+        require!(!calendar.is_closed_at(current_timestamp), HookError::TradingIsClosed);
+    }
+
     let seconds_in_day: i64 = 86_400;
-    let seconds_since_midnight = current_timestamp % seconds_in_day;
+    let local_timestamp = current_timestamp + config.utc_offset_minutes as i64 * 60;
+
+    // 1970-01-01 was a Thursday; bit 0 of `trading_days` is Monday
+    let weekday = (local_timestamp.div_euclid(seconds_in_day) + 3).rem_euclid(7);
+    require!(config.trading_days & (1 << weekday) != 0, HookError::TradingIsClosed);
+
+    let seconds_since_midnight = local_timestamp.rem_euclid(seconds_in_day);
     let current_minute = (seconds_since_midnight / 60) as u16; 
 
-    let is_open = if open_minute < close_minute {
+    match sessions.filter(|sessions| !sessions.sessions.is_empty()) {
+        Some(sessions) => require!(
+            sessions
+                .sessions
+                .iter()
+                .any(|session| is_within_session(current_minute, session.open_minute, session.close_minute)),
+            HookError::TradingIsClosed
+        ),
+        None => {
+            if let (Some(open_minute), Some(close_minute)) = (config.open_minute, config.close_minute) {
+                require!(
+                    is_within_session(current_minute, open_minute, close_minute),
+                    HookError::TradingIsClosed
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Sessions must be same-day intervals, in order and without overlaps.
+pub fn validate_sessions(sessions: &[TradingSession]) -> Result<()> {
+    require!(sessions.len() <= MAX_TRADING_SESSIONS, HookError::InvalidTradingSessions);
+
+    let mut previous_close = 0;
+    for session in sessions {
+        require!(
+            session.open_minute < session.close_minute
+                && session.close_minute <= TradingSession::MINUTES_PER_DAY
+                && session.open_minute >= previous_close,
+            HookError::InvalidTradingSessions
+        );
+        previous_close = session.close_minute;
+    }
+    Ok(())
+}
+
+pub fn is_within_session(current_minute: u16, open_minute: u16, close_minute: u16) -> bool {
+    if open_minute < close_minute {
         current_minute >= open_minute && current_minute < close_minute
     } else if open_minute > close_minute {
         current_minute >= open_minute || current_minute < close_minute
     } else {
         false 
-    };
-
-    require!(is_open, HookError::TradingIsClosed);
-    Ok(())
+    }
 }
 
 #[derive(Accounts)]
@@ -262,93 +975,756 @@ pub struct InitializeRegistry<'info> {
 }
 
 #[derive(Accounts)]
-pub struct UpdateConfig<'info> {
-    pub owner: Signer<'info>,
-    
+pub struct UpdateExtraAccountMetaList<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
     #[account(
-        mut,
         seeds = [b"config", mint.key().as_ref()],
-        bump,
-        has_one = owner @ HookError::Unauthorized 
+        bump
     )]
     pub config: Account<'info, ConfigAccount>,
+
     pub mint: InterfaceAccount<'info, Mint>,
-}
 
-#[derive(Accounts)]
-pub struct EditConfig<'info>{
-    pub owner: Signer<'info>,
-    
+    /// CHECK: ExtraAccountMetaList Account
     #[account(
         mut,
-        seeds = [b"config", mint.key().as_ref()],
-        bump,
-        has_one = owner @ HookError::Unauthorized 
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump
     )]
-    pub config: Account<'info, ConfigAccount>,
-    pub mint: InterfaceAccount<'info, Mint>,
+    pub extra_account_meta_list: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct AddToWhitelist<'info> {
+pub struct MigrateConfig<'info> {
     #[account(mut)]
-    pub payer: Signer<'info>,
-
     pub owner: Signer<'info>,
 
+    /// CHECK: Deserialized by hand, as the legacy layout no longer matches `ConfigAccount`
     #[account(
+        mut,
         seeds = [b"config", mint.key().as_ref()],
-        bump,
-        has_one = owner @ HookError::Unauthorized 
+        bump
     )]
-    pub config: Account<'info, ConfigAccount>,
+    pub config: UncheckedAccount<'info>,
 
     pub mint: InterfaceAccount<'info, Mint>,
 
-    /// CHECK: The user's wallet address to whitelist
-    pub user_pubkey: AccountInfo<'info>,
-
+    /// CHECK: ExtraAccountMetaList Account
     #[account(
-        init,
-        payer = payer,
-        space = 8,
-        seeds = [b"whitelist", mint.key().as_ref(), user_pubkey.key().as_ref()],
+        mut,
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
         bump
     )]
-    pub whitelist_marker: Account<'info, WhitelistMarker>,
+    pub extra_account_meta_list: AccountInfo<'info>,
 
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct RemoveFromWhitelist<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
-
-    pub owner: Signer<'info>,
-
+pub struct UpdateConfig<'info> {
+    pub authority: Signer<'info>,
+    
     #[account(
+        mut,
         seeds = [b"config", mint.key().as_ref()],
         bump,
-        has_one = owner @ HookError::Unauthorized 
+        constraint = config.is_authorized(&authority.key(), role_marker.is_some()) @ HookError::Unauthorized
     )]
     pub config: Account<'info, ConfigAccount>,
-
     pub mint: InterfaceAccount<'info, Mint>,
 
-    /// CHECK: The user's wallet address to revoke
-    pub user_pubkey: AccountInfo<'info>,
-
+    /// Lets a config admin act in place of the owner
     #[account(
-        mut,
-        close = payer,
-        seeds = [b"whitelist", mint.key().as_ref(), user_pubkey.key().as_ref()],
+        seeds = [b"role", mint.key().as_ref(), &[Role::ConfigAdmin as u8], authority.key().as_ref()],
         bump
     )]
-    pub whitelist_marker: Account<'info, WhitelistMarker>,
-}
+    pub role_marker: Option<Account<'info, RoleMarker>>,
 
-#[derive(Accounts)]
+    /// Pays for `pending_change` when the config is timelocked
+    #[account(mut)]
+    pub payer: Option<Signer<'info>>,
+
+    /// Receives the change instead of the config when it is timelocked
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PendingConfigChange::INIT_SPACE,
+        seeds = [b"pending-config", mint.key().as_ref()],
+        bump
+    )]
+    pub pending_change: Option<Account<'info, PendingConfigChange>>,
+
+    pub system_program: Option<Program<'info, System>>,
+}
+
+#[derive(Accounts)]
+pub struct ApplyPending<'info> {
+    /// CHECK: Refunded the pending change's rent, checked against `pending_change.payer`
+    #[account(mut)]
+    pub payer: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config", mint.key().as_ref()],
+        bump
+    )]
+    pub config: Account<'info, ConfigAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        close = payer,
+        has_one = payer @ HookError::Unauthorized,
+        seeds = [b"pending-config", mint.key().as_ref()],
+        bump = pending_change.bump
+    )]
+    pub pending_change: Account<'info, PendingConfigChange>,
+
+    /// CHECK: Instructions sysvar, only needed to change `nft_mint_address`
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions_sysvar: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct CancelPending<'info> {
+    pub authority: Signer<'info>,
+
+    /// CHECK: Refunded the pending change's rent, checked against `pending_change.payer`
+    #[account(mut)]
+    pub payer: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"config", mint.key().as_ref()],
+        bump,
+        constraint = config.is_authorized(&authority.key(), role_marker.is_some()) @ HookError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Lets a config admin act in place of the owner
+    #[account(
+        seeds = [b"role", mint.key().as_ref(), &[Role::ConfigAdmin as u8], authority.key().as_ref()],
+        bump
+    )]
+    pub role_marker: Option<Account<'info, RoleMarker>>,
+
+    #[account(
+        mut,
+        close = payer,
+        has_one = payer @ HookError::Unauthorized,
+        seeds = [b"pending-config", mint.key().as_ref()],
+        bump = pending_change.bump
+    )]
+    pub pending_change: Account<'info, PendingConfigChange>,
+}
+
+#[derive(Accounts)]
+pub struct PauseTrading<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config", mint.key().as_ref()],
+        bump,
+        constraint = config.is_authorized(&authority.key(), role_marker.is_some()) @ HookError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Lets a pauser act in place of the owner
+    #[account(
+        seeds = [b"role", mint.key().as_ref(), &[Role::Pauser as u8], authority.key().as_ref()],
+        bump
+    )]
+    pub role_marker: Option<Account<'info, RoleMarker>>,
+}
+
+#[derive(Accounts)]
+pub struct ManageOwnership<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config", mint.key().as_ref()],
+        bump,
+        has_one = owner @ HookError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+}
+
+#[derive(Accounts)]
+pub struct RenounceOwnership<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config", mint.key().as_ref()],
+        bump,
+        has_one = owner @ HookError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Must not exist, as nobody could cancel a queued change once the owner is gone
+    #[account(
+        seeds = [b"pending-config", mint.key().as_ref()],
+        bump,
+        constraint = pending_change.data_is_empty() @ HookError::ChangePending
+    )]
+    pub pending_change: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptOwner<'info> {
+    pub new_owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config", mint.key().as_ref()],
+        bump,
+        constraint = config.pending_owner == Some(new_owner.key()) @ HookError::NotPendingOwner
+    )]
+    pub config: Account<'info, ConfigAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+}
+
+#[derive(Accounts)]
+pub struct EditConfig<'info>{
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"config", mint.key().as_ref()],
+        bump,
+        constraint = config.is_authorized(&authority.key(), role_marker.is_some()) @ HookError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Lets a config admin act in place of the owner
+    #[account(
+        seeds = [b"role", mint.key().as_ref(), &[Role::ConfigAdmin as u8], authority.key().as_ref()],
+        bump
+    )]
+    pub role_marker: Option<Account<'info, RoleMarker>>,
+
+    /// Pays for `pending_change` when the config is timelocked
+    #[account(mut)]
+    pub payer: Option<Signer<'info>>,
+
+    /// Receives the change instead of the config when it is timelocked
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PendingConfigChange::INIT_SPACE,
+        seeds = [b"pending-config", mint.key().as_ref()],
+        bump
+    )]
+    pub pending_change: Option<Account<'info, PendingConfigChange>>,
+
+    pub system_program: Option<Program<'info, System>>,
+
+    /// CHECK: Instructions sysvar, only needed to change `nft_mint_address`
+    #[account(address = sysvar_instructions::ID)]
+    pub instructions_sysvar: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+#[instruction(role: Role)]
+pub struct GrantRole<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", mint.key().as_ref()],
+        bump,
+        has_one = owner @ HookError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: The wallet receiving the role
+    pub holder: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8,
+        seeds = [b"role", mint.key().as_ref(), &[role as u8], holder.key().as_ref()],
+        bump
+    )]
+    pub role_marker: Account<'info, RoleMarker>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(role: Role)]
+pub struct RevokeRole<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", mint.key().as_ref()],
+        bump,
+        has_one = owner @ HookError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: The wallet losing the role
+    pub holder: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"role", mint.key().as_ref(), &[role as u8], holder.key().as_ref()],
+        bump
+    )]
+    pub role_marker: Account<'info, RoleMarker>,
+}
+
+#[derive(Accounts)]
+pub struct AddToWhitelist<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", mint.key().as_ref()],
+        bump,
+        constraint = config.is_authorized(&authority.key(), role_marker.is_some()) @ HookError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: The user's wallet address to whitelist
+    pub user_pubkey: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8,
+        seeds = [b"whitelist", mint.key().as_ref(), user_pubkey.key().as_ref()],
+        bump
+    )]
+    pub whitelist_marker: Account<'info, WhitelistMarker>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Lets a compliance officer act in place of the owner
+    #[account(
+        seeds = [b"role", mint.key().as_ref(), &[Role::ComplianceOfficer as u8], authority.key().as_ref()],
+        bump
+    )]
+    pub role_marker: Option<Account<'info, RoleMarker>>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveFromWhitelist<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", mint.key().as_ref()],
+        bump,
+        constraint = config.is_authorized(&authority.key(), role_marker.is_some()) @ HookError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: The user's wallet address to revoke
+    pub user_pubkey: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"whitelist", mint.key().as_ref(), user_pubkey.key().as_ref()],
+        bump
+    )]
+    pub whitelist_marker: Account<'info, WhitelistMarker>,
+
+    /// Lets a compliance officer act in place of the owner
+    #[account(
+        seeds = [b"role", mint.key().as_ref(), &[Role::ComplianceOfficer as u8], authority.key().as_ref()],
+        bump
+    )]
+    pub role_marker: Option<Account<'info, RoleMarker>>,
+}
+
+#[derive(Accounts)]
+pub struct AddToBlacklist<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", mint.key().as_ref()],
+        bump,
+        constraint = config.is_authorized(&authority.key(), role_marker.is_some()) @ HookError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: The user's wallet address to blacklist
+    pub user_pubkey: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8,
+        seeds = [b"blacklist", mint.key().as_ref(), user_pubkey.key().as_ref()],
+        bump
+    )]
+    pub blacklist_marker: Account<'info, BlacklistMarker>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Lets a compliance officer act in place of the owner
+    #[account(
+        seeds = [b"role", mint.key().as_ref(), &[Role::ComplianceOfficer as u8], authority.key().as_ref()],
+        bump
+    )]
+    pub role_marker: Option<Account<'info, RoleMarker>>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveFromBlacklist<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", mint.key().as_ref()],
+        bump,
+        constraint = config.is_authorized(&authority.key(), role_marker.is_some()) @ HookError::Unauthorized
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: The user's wallet address to remove from the blacklist
+    pub user_pubkey: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"blacklist", mint.key().as_ref(), user_pubkey.key().as_ref()],
+        bump
+    )]
+    pub blacklist_marker: Account<'info, BlacklistMarker>,
+
+    /// Lets a compliance officer act in place of the owner
+    #[account(
+        seeds = [b"role", mint.key().as_ref(), &[Role::ComplianceOfficer as u8], authority.key().as_ref()],
+        bump
+    )]
+    pub role_marker: Option<Account<'info, RoleMarker>>,
+}
+
+#[derive(Accounts)]
+pub struct AddBalanceExemption<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", mint.key().as_ref()],
+        bump,
+        has_one = owner @ HookError::Unauthorized 
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: The wallet (e.g. a pool, the treasury or the ICO vault) allowed to exceed the max balance
+    pub user_pubkey: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8,
+        seeds = [b"balance-exempt", mint.key().as_ref(), user_pubkey.key().as_ref()],
+        bump
+    )]
+    pub balance_exempt_marker: Account<'info, BalanceExemptMarker>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveBalanceExemption<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", mint.key().as_ref()],
+        bump,
+        has_one = owner @ HookError::Unauthorized 
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: The wallet whose exemption is revoked
+    pub user_pubkey: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"balance-exempt", mint.key().as_ref(), user_pubkey.key().as_ref()],
+        bump
+    )]
+    pub balance_exempt_marker: Account<'info, BalanceExemptMarker>,
+}
+
+#[derive(Accounts)]
+pub struct AddExemption<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", mint.key().as_ref()],
+        bump,
+        has_one = owner @ HookError::Unauthorized 
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: The wallet or token account that bypasses every hook rule but the halt and the blacklist
+    pub exempt_pubkey: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8,
+        seeds = [b"exempt", mint.key().as_ref(), exempt_pubkey.key().as_ref()],
+        bump
+    )]
+    pub exempt_marker: Account<'info, ExemptMarker>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveExemption<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", mint.key().as_ref()],
+        bump,
+        has_one = owner @ HookError::Unauthorized 
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: The wallet or token account whose exemption is revoked
+    pub exempt_pubkey: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        close = payer,
+        seeds = [b"exempt", mint.key().as_ref(), exempt_pubkey.key().as_ref()],
+        bump
+    )]
+    pub exempt_marker: Account<'info, ExemptMarker>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeCalendar<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", mint.key().as_ref()],
+        bump,
+        has_one = owner @ HookError::Unauthorized 
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + TradingCalendar::INIT_SPACE,
+        seeds = [b"calendar", mint.key().as_ref()],
+        bump
+    )]
+    pub trading_calendar: Account<'info, TradingCalendar>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateCalendar<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", mint.key().as_ref()],
+        bump,
+        has_one = owner @ HookError::Unauthorized 
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"calendar", mint.key().as_ref()],
+        bump = trading_calendar.bump
+    )]
+    pub trading_calendar: Account<'info, TradingCalendar>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTradingSessions<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", mint.key().as_ref()],
+        bump,
+        has_one = owner @ HookError::Unauthorized 
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + TradingSessions::INIT_SPACE,
+        seeds = [b"sessions", mint.key().as_ref()],
+        bump
+    )]
+    pub trading_sessions: Account<'info, TradingSessions>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateTradingSessions<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", mint.key().as_ref()],
+        bump,
+        has_one = owner @ HookError::Unauthorized 
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"sessions", mint.key().as_ref()],
+        bump = trading_sessions.bump
+    )]
+    pub trading_sessions: Account<'info, TradingSessions>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterNftPass<'info> {
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"config", mint.key().as_ref()],
+        bump
+    )]
+    pub config: Account<'info, ConfigAccount>,
+
+    /// The collection NFT the holder gates their transfers with
+    #[account(mint::token_program = token_program)]
+    pub nft_mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Metaplex metadata of `nft_mint`, checked against `config.nft_collection`
+    pub nft_metadata: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        token::mint = nft_mint,
+        token::authority = holder,
+        token::token_program = token_program
+    )]
+    pub holder_nft_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Token account the NFT is moved into; the holder remains its owner and
+    /// can withdraw the NFT or close the pass at any time
+    #[account(
+        init,
+        payer = holder,
+        seeds = [b"nft-pass", mint.key().as_ref(), holder.key().as_ref()],
+        bump,
+        token::mint = nft_mint,
+        token::authority = holder,
+        token::token_program = token_program
+    )]
+    pub nft_pass: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeWalletState<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: The wallet whose outgoing transfers are tracked
+    pub wallet: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + WalletState::INIT_SPACE,
+        seeds = [b"wallet-state", mint.key().as_ref(), wallet.key().as_ref()],
+        bump
+    )]
+    pub wallet_state: Account<'info, WalletState>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
 pub struct ExecuteTransfer<'info> {
     #[account(
         token::mint = mint,
@@ -393,22 +1769,360 @@ pub struct ExecuteTransfer<'info> {
 
     /// CHECK: User's NFT ATA (Dynamically Resolved via MetaList)
     pub nft_token_account: UncheckedAccount<'info>,                        // Index 10
+
+    /// CHECK: Validated dynamically
+    #[account(
+        seeds = [b"blacklist", mint.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub source_blacklist_marker: UncheckedAccount<'info>,                  // Index 11
+
+    /// CHECK: Validated dynamically
+    #[account(
+        seeds = [b"blacklist", mint.key().as_ref(), destination_token.owner.as_ref()],
+        bump
+    )]
+    pub destination_blacklist_marker: UncheckedAccount<'info>,             // Index 12
+
+    /// CHECK: Validated dynamically
+    #[account(
+        seeds = [b"whitelist", mint.key().as_ref(), destination_token.owner.as_ref()],
+        bump
+    )]
+    pub destination_whitelist_marker: UncheckedAccount<'info>,             // Index 13
+
+    /// CHECK: Validated dynamically, only read when a per-wallet rule (volume or cooldown) is enabled
+    #[account(
+        mut,
+        seeds = [b"wallet-state", mint.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub wallet_state: UncheckedAccount<'info>,                             // Index 14
+
+    /// CHECK: Validated dynamically
+    #[account(
+        seeds = [b"balance-exempt", mint.key().as_ref(), destination_token.owner.as_ref()],
+        bump
+    )]
+    pub balance_exempt_marker: UncheckedAccount<'info>,                    // Index 15
+
+    /// CHECK: Validated dynamically
+    #[account(
+        seeds = [b"exempt", mint.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub source_owner_exempt_marker: UncheckedAccount<'info>,               // Index 16
+
+    /// CHECK: Validated dynamically
+    #[account(
+        seeds = [b"exempt", mint.key().as_ref(), source_token.key().as_ref()],
+        bump
+    )]
+    pub source_token_exempt_marker: UncheckedAccount<'info>,               // Index 17
+
+    /// CHECK: Validated dynamically
+    #[account(
+        seeds = [b"exempt", mint.key().as_ref(), destination_token.owner.as_ref()],
+        bump
+    )]
+    pub destination_owner_exempt_marker: UncheckedAccount<'info>,          // Index 18
+
+    /// CHECK: Validated dynamically
+    #[account(
+        seeds = [b"exempt", mint.key().as_ref(), destination_token.key().as_ref()],
+        bump
+    )]
+    pub destination_token_exempt_marker: UncheckedAccount<'info>,          // Index 19
+
+    /// CHECK: Validated dynamically, only read when the trading window is enabled
+    #[account(
+        seeds = [b"calendar", mint.key().as_ref()],
+        bump
+    )]
+    pub trading_calendar: UncheckedAccount<'info>,                         // Index 20
+
+    /// CHECK: Validated dynamically, only read when the trading window is enabled
+    #[account(
+        seeds = [b"sessions", mint.key().as_ref()],
+        bump
+    )]
+    pub trading_sessions: UncheckedAccount<'info>,                         // Index 21
+    // Index 22 (collection gating only) arrives as a remaining account
 }
 
 #[account]
 #[derive(InitSpace)]
 pub struct ConfigAccount {
     pub owner: Pubkey,
+    /// Proposed by `propose_owner`, becomes the owner once it calls `accept_owner`.
+    pub pending_owner: Option<Pubkey>,
     pub mint: Pubkey,
     pub nft_mint_address: Pubkey,
+    /// Verified Metaplex collection gating transfers instead of `nft_mint_address`.
+    pub nft_collection: Option<Pubkey>,
+    /// Requires the sender to be whitelisted.
     pub whitelist_enabled: bool,
+    /// Requires the recipient to be whitelisted.
+    pub destination_whitelist_enabled: bool,
     pub trading_time_enabled: bool,
     pub max_transfer_enabled: bool,
     pub nft_gated: bool,
+    pub blacklist_enabled: bool,
+    pub volume_limit_enabled: bool,
+    pub cooldown_enabled: bool,
+    pub max_wallet_balance_enabled: bool,
     pub open_minute: Option<u16>, 
     pub close_minute: Option<u16>,
     pub max_transfer_amount: u64, 
     pub min_transfer_amount: u64, 
+    /// Length of the per-wallet volume window, in seconds.
+    pub volume_window: i64,
+    /// Most a wallet may send within one volume window.
+    pub max_volume_per_window: u64,
+    /// Minimum number of seconds between outgoing transfers from one wallet.
+    pub cooldown_seconds: i64,
+    /// Most a single wallet may hold after receiving a transfer.
+    pub max_wallet_balance: u64,
+    /// Weekdays trading is open on, one bit per day from Monday (bit 0) to Sunday (bit 6).
+    pub trading_days: u8,
+    /// Offset of the trading timezone from UTC, applied to the trading days and hours.
+    pub utc_offset_minutes: i16,
+    /// Unix timestamp before which only exempt transfers go through, 0 for none.
+    pub trading_starts_at: i64,
+    /// Set by `halt` to freeze every transfer until `resume`.
+    pub halted: bool,
+    /// Delay, in seconds, before `update_flags` and `edit_config` take effect, 0 for none.
+    pub timelock_seconds: i64,
+}
+
+impl ConfigAccount {
+    pub const ALL_TRADING_DAYS: u8 = 0b0111_1111;
+    pub const MAX_UTC_OFFSET_MINUTES: i16 = 14 * 60;
+
+    /// The owner may always act; a role holder only while the config still has
+    /// an owner, so renouncing ownership also retires every role.
+    pub fn is_authorized(&self, authority: &Pubkey, has_role: bool) -> bool {
+        *authority == self.owner || (has_role && self.owner != Pubkey::default())
+    }
+
+    pub fn apply_change(&mut self, change: &ConfigChange) -> Result<()> {
+        match change {
+            ConfigChange::Flags(flags) => {
+                self.whitelist_enabled = flags.whitelist_enabled;
+                self.destination_whitelist_enabled = flags.destination_whitelist_enabled;
+                self.volume_limit_enabled = flags.volume_limit_enabled;
+                self.cooldown_enabled = flags.cooldown_enabled;
+                self.max_wallet_balance_enabled = flags.max_wallet_balance_enabled;
+                self.trading_time_enabled = flags.trading_time_enabled;
+                self.max_transfer_enabled = flags.max_transfer_enabled;
+                self.nft_gated = flags.nft_gated;
+                self.blacklist_enabled = flags.blacklist_enabled;
+                Ok(())
+            }
+            ConfigChange::Edit(edit) => self.apply_edit(edit),
+        }
+    }
+
+    fn apply_edit(&mut self, edit: &ConfigEdit) -> Result<()> {
+        if let (Some(open), Some(close)) = (edit.open_minute, edit.close_minute) {
+            self.open_minute = Some(open);
+            self.close_minute = Some(close);
+        }
+
+        if let (Some(max), Some(min)) = (edit.max_transfer_amount, edit.min_transfer_amount) {
+            require!(max != 0 && min != 0, HookError::InvalidTransferAmount);
+            self.max_transfer_amount = max;
+            self.min_transfer_amount = min;
+        }
+
+        if let Some(mint_address) = edit.nft_mint_address {
+            require!(mint_address != Pubkey::default(), HookError::InvalidPubkey);
+            self.nft_mint_address = mint_address;
+        }
+
+        if let (Some(window), Some(max_volume)) = (edit.volume_window, edit.max_volume_per_window) {
+            require!(window > 0 && max_volume != 0, HookError::InvalidVolumeLimit);
+            self.volume_window = window;
+            self.max_volume_per_window = max_volume;
+        }
+
+        if let Some(cooldown) = edit.cooldown_seconds {
+            require!(cooldown >= 0, HookError::InvalidCooldown);
+            self.cooldown_seconds = cooldown;
+        }
+
+        if let Some(max_balance) = edit.max_wallet_balance {
+            require!(max_balance != 0, HookError::InvalidTransferAmount);
+            self.max_wallet_balance = max_balance;
+        }
+
+        if let Some(days) = edit.trading_days {
+            require!(days <= Self::ALL_TRADING_DAYS, HookError::InvalidTradingDays);
+            self.trading_days = days;
+        }
+
+        if let Some(offset) = edit.utc_offset_minutes {
+            require!(
+                (-Self::MAX_UTC_OFFSET_MINUTES..=Self::MAX_UTC_OFFSET_MINUTES).contains(&offset),
+                HookError::InvalidUtcOffset
+            );
+            self.utc_offset_minutes = offset;
+        }
+
+        if let Some(starts_at) = edit.trading_starts_at {
+            let now = Clock::get()?.unix_timestamp;
+            // Only a start scheduled by `initialize_registry` that is still ahead
+            // can be moved; a token that is trading (start 0 or passed) stays open
+            require!(
+                self.trading_starts_at != 0 && now < self.trading_starts_at,
+                HookError::TradingAlreadyStarted
+            );
+            require!(starts_at > now, HookError::InvalidTradingStart);
+            self.trading_starts_at = starts_at;
+        }
+
+        if let Some(timelock) = edit.timelock_seconds {
+            require!(timelock >= 0, HookError::InvalidTimelock);
+            self.timelock_seconds = timelock;
+        }
+
+        Ok(())
+    }
+}
+
+/// Layout of `ConfigAccount` before the blacklist and later features, kept
+/// for `migrate_config`.
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyConfigAccount {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub nft_mint_address: Pubkey,
+    pub whitelist_enabled: bool,
+    pub trading_time_enabled: bool,
+    pub max_transfer_enabled: bool,
+    pub nft_gated: bool,
+    pub open_minute: Option<u16>,
+    pub close_minute: Option<u16>,
+    pub max_transfer_amount: u64,
+    pub min_transfer_amount: u64,
+}
+
+impl LegacyConfigAccount {
+    pub const LEN: usize = 32 + 32 + 32 + 4 + 3 + 3 + 8 + 8;
+}
+
+/// The arguments of `update_flags`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct ConfigFlags {
+    pub whitelist_enabled: bool,
+    pub trading_time_enabled: bool,
+    pub max_transfer_enabled: bool,
+    pub nft_gated: bool,
+    pub blacklist_enabled: bool,
+    pub destination_whitelist_enabled: bool,
+    pub volume_limit_enabled: bool,
+    pub cooldown_enabled: bool,
+    pub max_wallet_balance_enabled: bool,
+}
+
+/// The arguments of `edit_config`; `None` leaves a setting unchanged.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct ConfigEdit {
+    pub open_minute: Option<u16>,
+    pub close_minute: Option<u16>,
+    pub max_transfer_amount: Option<u64>,
+    pub min_transfer_amount: Option<u64>,
+    pub nft_mint_address: Option<Pubkey>,
+    pub volume_window: Option<i64>,
+    pub max_volume_per_window: Option<u64>,
+    pub cooldown_seconds: Option<i64>,
+    pub max_wallet_balance: Option<u64>,
+    pub trading_days: Option<u8>,
+    pub utc_offset_minutes: Option<i16>,
+    pub trading_starts_at: Option<i64>,
+    pub timelock_seconds: Option<i64>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub enum ConfigChange {
+    Flags(ConfigFlags),
+    Edit(ConfigEdit),
+}
+
+/// A change queued by `update_flags` or `edit_config` while the config has a
+/// timelock. Only one change can be pending per mint.
+#[account]
+#[derive(InitSpace)]
+pub struct PendingConfigChange {
+    pub mint: Pubkey,
+    /// Paid the rent and gets it back when the change is applied or cancelled.
+    pub payer: Pubkey,
+    pub change: ConfigChange,
+    /// Unix timestamp from which `apply_pending` can commit the change.
+    pub effective_at: i64,
+    pub bump: u8,
+}
+
+/// Admin duties the owner can delegate per mint with `grant_role`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// Adds and removes whitelist and blacklist entries.
+    ComplianceOfficer,
+    /// Halts and resumes trading.
+    Pauser,
+    /// Calls `update_flags` and `edit_config`.
+    ConfigAdmin,
+}
+
+pub const MAX_CLOSED_RANGES: usize = 32;
+
+/// A period, in unix seconds, during which trading is closed (`end_time` exclusive).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct ClosedRange {
+    pub start_time: i64,
+    pub end_time: i64,
+}
+
+/// Owner-managed closed dates (holidays, maintenance, ...) checked alongside
+/// the trading days and hours.
+#[account]
+#[derive(InitSpace)]
+pub struct TradingCalendar {
+    pub mint: Pubkey,
+    #[max_len(MAX_CLOSED_RANGES)]
+    pub closed_ranges: Vec<ClosedRange>,
+    pub bump: u8,
+}
+
+impl TradingCalendar {
+    pub fn is_closed_at(&self, timestamp: i64) -> bool {
+        self.closed_ranges
+            .iter()
+            .any(|range| timestamp >= range.start_time && timestamp < range.end_time)
+    }
+}
+
+pub const MAX_TRADING_SESSIONS: usize = 8;
+
+/// A trading session within a day, in minutes (`close_minute` exclusive).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, InitSpace)]
+pub struct TradingSession {
+    pub open_minute: u16,
+    pub close_minute: u16,
+}
+
+impl TradingSession {
+    pub const MINUTES_PER_DAY: u16 = 24 * 60;
+}
+
+/// Daily trading sessions (e.g. a morning and an afternoon session around a lunch break).
+#[account]
+#[derive(InitSpace)]
+pub struct TradingSessions {
+    pub mint: Pubkey,
+    #[max_len(MAX_TRADING_SESSIONS)]
+    pub sessions: Vec<TradingSession>,
+    pub bump: u8,
 }
 
 #[account]
@@ -416,6 +2130,50 @@ pub struct WhitelistMarker {
     
 }
 
+#[account]
+pub struct BlacklistMarker {}
+
+#[account]
+pub struct BalanceExemptMarker {}
+
+#[account]
+pub struct ExemptMarker {}
+
+#[account]
+pub struct RoleMarker {}
+
+/// Per-wallet transfer history, created with `initialize_wallet_state` and
+/// updated by `execute` on every outgoing transfer.
+#[account]
+#[derive(InitSpace)]
+pub struct WalletState {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub window_start: i64,
+    pub window_volume: u64,
+    /// Unix timestamp of the wallet's last outgoing transfer, 0 if none.
+    pub last_transfer_at: i64,
+    pub bump: u8,
+}
+
+impl WalletState {
+    /// Adds `amount` to the current window. Windows are fixed, not rolling:
+    /// they are aligned to multiples of `window` seconds since the unix epoch,
+    /// so the volume resets at each boundary.
+    pub fn record_volume(&mut self, amount: u64, now: i64, window: i64, max_volume: u64) -> Result<()> {
+        let current_start = now.checked_rem_euclid(window).map_or(now, |offset| now - offset);
+        if self.window_start != current_start {
+            self.window_start = current_start;
+            self.window_volume = 0;
+        }
+
+        let new_volume = self.window_volume.checked_add(amount).ok_or(HookError::ExceedsVolumeLimit)?;
+        require!(new_volume <= max_volume, HookError::ExceedsVolumeLimit);
+        self.window_volume = new_volume;
+        Ok(())
+    }
+}
+
 #[error_code]
 pub enum HookError {
     #[msg("Trading is currently closed for this token")]
@@ -435,5 +2193,131 @@ pub enum HookError {
     #[msg("Invalide pub key")]
     InvalidPubkey,
     #[msg("Invalid transfer amount")]
-    InvalidTransferAmount
-}
\ No newline at end of file
+    InvalidTransferAmount,
+    #[msg("Sender is blacklisted")]
+    SenderBlacklisted,
+    #[msg("Recipient is blacklisted")]
+    RecipientBlacklisted,
+    #[msg("Recipient is not whitelisted")]
+    RecipientNotWhitelisted,
+    #[msg("Transfer exceeds the wallet's volume limit for this window")]
+    ExceedsVolumeLimit,
+    #[msg("Invalid volume limit")]
+    InvalidVolumeLimit,
+    #[msg("Wallet state account has not been initialized")]
+    WalletStateNotInitialized,
+    #[msg("Wallet must wait for its transfer cooldown to pass")]
+    TransferCooldownActive,
+    #[msg("Invalid cooldown")]
+    InvalidCooldown,
+    #[msg("Transfer would exceed the recipient's maximum wallet balance")]
+    ExceedsMaxWalletBalance,
+    #[msg("Invalid trading days")]
+    InvalidTradingDays,
+    #[msg("Invalid UTC offset")]
+    InvalidUtcOffset,
+    #[msg("Invalid closed range")]
+    InvalidClosedRange,
+    #[msg("Trading calendar is full")]
+    CalendarFull,
+    #[msg("Closed range not found")]
+    ClosedRangeNotFound,
+    #[msg("Trading sessions must be ordered, non-overlapping intervals within a day")]
+    InvalidTradingSessions,
+    #[msg("Trading is halted")]
+    TradingHalted,
+    #[msg("Trading has not started yet")]
+    TradingNotStarted,
+    #[msg("Invalid trading start time")]
+    InvalidTradingStart,
+    #[msg("Sender's NFT pass is missing or does not match")]
+    InvalidNftPass,
+    #[msg("NFT is not a verified member of the required collection")]
+    NotInCollection,
+    #[msg("Signer is not the pending owner")]
+    NotPendingOwner,
+    #[msg("Invalid timelock")]
+    InvalidTimelock,
+    #[msg("A pending change account is required exactly when the config is timelocked")]
+    InvalidPendingChange,
+    #[msg("The pending change's timelock has not elapsed")]
+    TimelockNotElapsed,
+    #[msg("Changing the NFT mint requires update_extra_account_meta_list later in the same transaction")]
+    MetaListRebuildRequired,
+    #[msg("A config change is still queued; apply or cancel it first")]
+    ChangePending,
+    #[msg("Trading has already started")]
+    TradingAlreadyStarted,
+    #[msg("Config account is not in the legacy layout")]
+    NotLegacyConfig
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Borsh layout of a Metaplex `Metadata` account up to its collection.
+    fn metadata(creators: Option<usize>, collection: Option<(bool, Pubkey)>) -> Vec<u8> {
+        let mut data = vec![4]; // Key::MetadataV1
+        data.extend_from_slice(&[1; 32]); // update_authority
+        data.extend_from_slice(&[2; 32]); // mint
+        for field in ["Pass #1", "PASS", "https://example.com/1.json"] {
+            data.extend_from_slice(&(field.len() as u32).to_le_bytes());
+            data.extend_from_slice(field.as_bytes());
+        }
+        data.extend_from_slice(&500u16.to_le_bytes());
+        match creators {
+            Some(count) => {
+                data.push(1);
+                data.extend_from_slice(&(count as u32).to_le_bytes());
+                for _ in 0..count {
+                    data.extend_from_slice(&[3; 32]);
+                    data.extend_from_slice(&[1, 100]); // verified, share
+                }
+            }
+            None => data.push(0),
+        }
+        data.extend_from_slice(&[1, 1]); // primary_sale_happened, is_mutable
+        data.extend_from_slice(&[1, 255]); // edition_nonce
+        data.extend_from_slice(&[1, 0]); // token_standard
+        match collection {
+            Some((verified, key)) => {
+                data.extend_from_slice(&[1, verified as u8]);
+                data.extend_from_slice(key.as_ref());
+            }
+            None => data.push(0),
+        }
+        data
+    }
+
+    #[test]
+    fn verified_collection_with_creators() {
+        let collection = Pubkey::new_unique();
+        assert_eq!(verified_collection(&metadata(Some(2), Some((true, collection)))), Some(collection));
+    }
+
+    #[test]
+    fn verified_collection_without_creators() {
+        let collection = Pubkey::new_unique();
+        assert_eq!(verified_collection(&metadata(None, Some((true, collection)))), Some(collection));
+    }
+
+    #[test]
+    fn unverified_collection_is_ignored() {
+        let collection = Pubkey::new_unique();
+        assert_eq!(verified_collection(&metadata(Some(1), Some((false, collection)))), None);
+        assert_eq!(verified_collection(&metadata(None, Some((false, collection)))), None);
+    }
+
+    #[test]
+    fn missing_collection_is_ignored() {
+        assert_eq!(verified_collection(&metadata(Some(1), None)), None);
+        assert_eq!(verified_collection(&metadata(None, None)), None);
+    }
+
+    #[test]
+    fn truncated_metadata_is_ignored() {
+        let data = metadata(Some(1), Some((true, Pubkey::new_unique())));
+        assert_eq!(verified_collection(&data[..data.len() - 1]), None);
+        assert_eq!(verified_collection(&data[..40]), None);
+    }
+}
@@ -11,6 +11,9 @@ import {
 } from "@solana/web3.js";
 import {
     TOKEN_PROGRAM_ID,
+    TOKEN_2022_PROGRAM_ID,
+    ExtensionType,
+    createInitializeTransferHookInstruction,
     createInitializeMintInstruction,
     getAssociatedTokenAddressSync,
     createAssociatedTokenAccountInstruction,
@@ -19,6 +22,7 @@ import {
     getMintLen,
 } from "@solana/spl-token";
 import { expect } from "chai";
+import { createHash } from "crypto";
 
 /** Extract a searchable error string from various Anchor / web3.js error formats. */
 function getErrorString(err: any): string {
@@ -32,6 +36,22 @@ function getErrorString(err: any): string {
     return parts.length > 0 ? parts.join("\n") : JSON.stringify(err);
 }
 
+const sha256 = (...parts: Buffer[]): Buffer => createHash("sha256").update(Buffer.concat(parts)).digest();
+
+/** Allowlist leaf as hashed on-chain: sha256(0x00 || buyer [|| allocation_le]). */
+function allowlistLeaf(buyer: PublicKey, allocation?: BN): Buffer {
+    return allocation
+        ? sha256(Buffer.from([0]), buyer.toBuffer(), allocation.toArrayLike(Buffer, "le", 8))
+        : sha256(Buffer.from([0]), buyer.toBuffer());
+}
+
+/** Interior node as hashed on-chain: sha256(0x01 || min(a, b) || max(a, b)). */
+function hashPair(a: Buffer, b: Buffer): Buffer {
+    return Buffer.compare(a, b) <= 0
+        ? sha256(Buffer.from([1]), a, b)
+        : sha256(Buffer.from([1]), b, a);
+}
+
 /**
  * Retry wrapper for Anchor .rpc() calls that may fail with stale blockhash.
  * Only retries on "Blockhash not found"; all other errors are thrown immediately.
@@ -80,6 +100,41 @@ describe("ico", () => {
         program.programId
     );
 
+    const [icoEscrowPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("ico_escrow_account"), mint.publicKey.toBuffer()],
+        program.programId
+    );
+
+    const [buyerPurchasePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("ico_purchase_account"), mint.publicKey.toBuffer(), buyer.publicKey.toBuffer()],
+        program.programId
+    );
+
+    /** Creates an SPL mint controlled by the creator and mints `supply` base units to the creator's ATA. */
+    async function createSaleMint(saleMint: Keypair, supply: number): Promise<PublicKey> {
+        const mintLen = getMintLen([]);
+        const mintLamports = await connection.getMinimumBalanceForRentExemption(mintLen);
+        const saleCreatorAta = getAssociatedTokenAddressSync(saleMint.publicKey, creator.publicKey, false, TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID);
+
+        const tx = new Transaction().add(
+            SystemProgram.createAccount({
+                fromPubkey: payer.publicKey,
+                newAccountPubkey: saleMint.publicKey,
+                space: mintLen,
+                lamports: mintLamports,
+                programId: TOKEN_PROGRAM_ID,
+            }),
+            createInitializeMintInstruction(saleMint.publicKey, decimals, creator.publicKey, null, TOKEN_PROGRAM_ID),
+            createAssociatedTokenAccountInstruction(
+                payer.publicKey, saleCreatorAta, creator.publicKey, saleMint.publicKey, TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID
+            ),
+            createMintToInstruction(saleMint.publicKey, saleCreatorAta, creator.publicKey, supply, [], TOKEN_PROGRAM_ID)
+        );
+        await sendAndConfirmTransaction(connection, tx, [payer.payer, saleMint, creator], { commitment: "confirmed" });
+
+        return saleCreatorAta;
+    }
+
     let creatorAta: PublicKey;
     let vaultAta: PublicKey;
     let buyerAta: PublicKey;
@@ -87,10 +142,15 @@ describe("ico", () => {
     // Parameters
     const decimals = 9;
     const initAmount = new BN(100_000 * Math.pow(10, decimals)); 
-    const softCap = new BN(5_000);   // 5,000 base units soft cap
-    const hardCap = new BN(10_000);  // 10,000 base units hard cap
+    // Caps are denominated in lamports raised
+    const softCap = new BN(5_000_000);   // 0.005 SOL soft cap
+    const hardCap = new BN(10_000_000);  // 0.01 SOL hard cap
+    // Prices are lamports per whole token, fixed-point with 9 decimals
+    const tokenScale = new BN(10).pow(new BN(decimals));
     // 1,000 lamports per base unit → buying 5,000 costs 5,000,000 lamports (0.005 SOL)
     const pricePerBaseUnit = new BN(1_000);
+    const pricePerToken = pricePerBaseUnit.mul(tokenScale);
+    const minPurchase = new BN(100);
 
     let startTime: number;
     let endTime: number;
@@ -156,6 +216,80 @@ describe("ico", () => {
         buyerAta = getAssociatedTokenAddressSync(mint.publicKey, buyer.publicKey, false, TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID);
     });
 
+    describe("migrate legacy config", () => {
+        // The protocol config in the layout before two-step ownership transfers, loaded from
+        // tests/fixtures by the local validator (see Anchor.toml): a fee of 100 owned by a seeded key
+        const legacyOwner = Keypair.fromSeed(Buffer.alloc(32, 13));
+
+        it("appends an empty pending owner to a legacy config", async function () {
+            const legacyInfo = await connection.getAccountInfo(configPda);
+            if (!legacyInfo || legacyInfo.data.length !== 8 + 41) {
+                // Fixtures only exist on the local test validator
+                this.skip();
+            }
+
+            await sendAndConfirmTransaction(
+                connection,
+                new Transaction().add(
+                    SystemProgram.transfer({
+                        fromPubkey: payer.publicKey,
+                        toPubkey: legacyOwner.publicKey,
+                        lamports: 0.05 * LAMPORTS_PER_SOL,
+                    })
+                ),
+                [payer.payer],
+                { commitment: "confirmed" }
+            );
+
+            try {
+                await program.methods.migrateConfig()
+                    .accountsPartial({ owner: payer.publicKey, config: configPda, systemProgram: SystemProgram.programId })
+                    .rpc({ commitment: "confirmed" });
+                expect.fail("Should fail for a signer that does not own the config");
+            } catch (err: any) {
+                expect(getErrorString(err)).to.contain("ProtocolOwnerMismatch");
+            }
+
+            await withRetry(() =>
+                program.methods.migrateConfig()
+                    .accountsPartial({ owner: legacyOwner.publicKey, config: configPda, systemProgram: SystemProgram.programId })
+                    .signers([legacyOwner])
+                    .rpc({ commitment: "confirmed" })
+            );
+
+            // The account is resized to the current layout with every field carried over
+            const migratedInfo = await connection.getAccountInfo(configPda);
+            expect(migratedInfo.data.length).to.equal(program.account.config.size);
+            expect(migratedInfo.lamports).to.be.at.least(
+                await connection.getMinimumBalanceForRentExemption(program.account.config.size)
+            );
+            const migrated = await program.account.config.fetch(configPda);
+            expect(migrated.owner.toBase58()).to.equal(legacyOwner.publicKey.toBase58());
+            expect(migrated.fee.toNumber()).to.equal(100);
+            expect(migrated.bump).to.equal(PublicKey.findProgramAddressSync([Buffer.from("config")], program.programId)[1]);
+            expect(migrated.pendingOwner).to.be.null;
+
+            try {
+                await program.methods.migrateConfig()
+                    .accountsPartial({ owner: legacyOwner.publicKey, config: configPda, systemProgram: SystemProgram.programId })
+                    .signers([legacyOwner])
+                    .rpc({ commitment: "confirmed" });
+                expect.fail("Should not migrate a config twice");
+            } catch (err: any) {
+                expect(getErrorString(err)).to.contain("NotLegacyConfig");
+            }
+
+            // Hand the migrated config to the payer, whom the rest of the suite expects as owner
+            await program.methods.proposeOwner(payer.publicKey)
+                .accountsPartial({ owner: legacyOwner.publicKey, config: configPda })
+                .signers([legacyOwner])
+                .rpc({ commitment: "confirmed" });
+            await program.methods.acceptOwner()
+                .accountsPartial({ newOwner: payer.publicKey, config: configPda })
+                .rpc({ commitment: "confirmed" });
+        });
+    });
+
     describe("initialize config", () => {
         it("initializes global config", async () => {
             const fee = new BN(100);
@@ -181,6 +315,42 @@ describe("ico", () => {
             const configAcc = await program.account.config.fetch(configPda);
             expect(configAcc.owner.toBase58()).to.equal(payer.publicKey.toBase58());
             expect(configAcc.fee.toNumber()).to.equal(100);
+            expect(configAcc.pendingOwner).to.be.null;
+        });
+
+        it("transfers protocol ownership in two steps", async () => {
+            await program.methods.proposeOwner(creator.publicKey)
+                .accountsPartial({ owner: payer.publicKey, config: configPda })
+                .rpc({ commitment: "confirmed" });
+
+            try {
+                await program.methods.acceptOwner()
+                    .accountsPartial({ newOwner: buyer.publicKey, config: configPda })
+                    .signers([buyer])
+                    .rpc({ commitment: "confirmed" });
+                expect.fail("Should fail for a signer that was not proposed");
+            } catch (err: any) {
+                expect(getErrorString(err)).to.contain("NotPendingOwner");
+            }
+
+            await program.methods.acceptOwner()
+                .accountsPartial({ newOwner: creator.publicKey, config: configPda })
+                .signers([creator])
+                .rpc({ commitment: "confirmed" });
+            expect((await program.account.config.fetch(configPda)).owner.toBase58()).to.equal(creator.publicKey.toBase58());
+
+            // Hand it back so later tests can withdraw fees to the payer
+            await program.methods.proposeOwner(payer.publicKey)
+                .accountsPartial({ owner: creator.publicKey, config: configPda })
+                .signers([creator])
+                .rpc({ commitment: "confirmed" });
+            await program.methods.acceptOwner()
+                .accountsPartial({ newOwner: payer.publicKey, config: configPda })
+                .rpc({ commitment: "confirmed" });
+
+            const configAcc = await program.account.config.fetch(configPda);
+            expect(configAcc.owner.toBase58()).to.equal(payer.publicKey.toBase58());
+            expect(configAcc.pendingOwner).to.be.null;
         });
     });
 
@@ -199,7 +369,11 @@ describe("ico", () => {
                     new BN(startTime),
                     new BN(endTime),
                     initAmount,
-                    pricePerBaseUnit
+                    pricePerToken,
+                    minPurchase,
+                    new BN(0), // no per-wallet maximum
+                    null, // no vesting
+                    null // no allowlist phase
                 )
                     .accountsPartial({
                         creator: creator.publicKey,
@@ -207,6 +381,7 @@ describe("ico", () => {
                         config: configPda,
                         icoConfigAccount: icoConfigPda,
                         icoVaultAccount: icoVaultPda,
+                        icoEscrowAccount: icoEscrowPda,
                         vaultAta: vaultAta,
                         creatorAta: creatorAta,
                         tokenProgram: TOKEN_PROGRAM_ID,
@@ -234,16 +409,18 @@ describe("ico", () => {
 
             try {
                 await withRetry(() =>
-                    program.methods.purchaseToken(amountToBuy)
+                    program.methods.purchaseToken(amountToBuy, [], null)
                         .accountsPartial({
                             buyer: buyer.publicKey,
                             mint: mint.publicKey,
                             config: configPda,
-                            creator: creator.publicKey,
                             icoConfigAccount: icoConfigPda,
                             icoVaultAccount: icoVaultPda,
+                            icoEscrowAccount: icoEscrowPda,
+                            icoPurchaseAccount: buyerPurchasePda,
                             vaultAta: vaultAta,
                             buyerAta: buyerAta,
+                            icoTiersAccount: null,
                             tokenProgram: TOKEN_PROGRAM_ID,
                             associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
                             systemProgram: SystemProgram.programId,
@@ -264,19 +441,21 @@ describe("ico", () => {
 
             const baseUnitsToBuy = new BN(5_000);
 
-            const balanceBefore = await connection.getBalance(creator.publicKey);
+            const balanceBefore = await connection.getBalance(icoEscrowPda);
 
             await withRetry(() =>
-                program.methods.purchaseToken(baseUnitsToBuy)
+                program.methods.purchaseToken(baseUnitsToBuy, [], null)
                     .accountsPartial({
                         buyer: buyer.publicKey,
                         mint: mint.publicKey,
                         config: configPda,
-                        creator: creator.publicKey,
                         icoConfigAccount: icoConfigPda,
                         icoVaultAccount: icoVaultPda,
+                        icoEscrowAccount: icoEscrowPda,
+                        icoPurchaseAccount: buyerPurchasePda,
                         vaultAta: vaultAta,
                         buyerAta: buyerAta,
+                        icoTiersAccount: null,
                         tokenProgram: TOKEN_PROGRAM_ID,
                         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
                         systemProgram: SystemProgram.programId,
@@ -285,32 +464,73 @@ describe("ico", () => {
                     .rpc({ skipPreflight: true, commitment: "confirmed" })
             );
 
-            const balanceAfter = await connection.getBalance(creator.publicKey);
+            const balanceAfter = await connection.getBalance(icoEscrowPda);
             const buyerTokenAcc = await connection.getTokenAccountBalance(buyerAta);
 
             // 5,000 base units with 9 decimals = 0.000005 tokens
             expect(buyerTokenAcc.value.uiAmount).to.equal(0.000005);
 
-            const expectedProfit = baseUnitsToBuy.toNumber() * pricePerBaseUnit.toNumber();
-            expect(balanceAfter - balanceBefore).to.equal(expectedProfit);
+            // SOL is held in the escrow PDA until the sale ends
+            const expectedEscrowed = baseUnitsToBuy.toNumber() * pricePerBaseUnit.toNumber();
+            expect(balanceAfter - balanceBefore).to.equal(expectedEscrowed);
+
+            const purchase = await program.account.icoPurchaseAccount.fetch(buyerPurchasePda);
+            expect(purchase.buyer.toBase58()).to.equal(buyer.publicKey.toBase58());
+            expect(purchase.amount.eq(baseUnitsToBuy)).to.be.true;
+            expect(purchase.amountPaid.toNumber()).to.equal(expectedEscrowed);
+
+            const icoConfig = await program.account.icoConfigAccount.fetch(icoConfigPda);
+            expect(icoConfig.totalRaised.toNumber()).to.equal(expectedEscrowed);
+            expect(icoConfig.tokensSold.eq(baseUnitsToBuy)).to.be.true;
+        });
+
+        it("fails if the purchase is below the minimum", async () => {
+            try {
+                await withRetry(() =>
+                    program.methods.purchaseToken(minPurchase.subn(1), [], null)
+                        .accountsPartial({
+                            buyer: buyer.publicKey,
+                            mint: mint.publicKey,
+                            config: configPda,
+                            icoConfigAccount: icoConfigPda,
+                            icoVaultAccount: icoVaultPda,
+                            icoEscrowAccount: icoEscrowPda,
+                            icoPurchaseAccount: buyerPurchasePda,
+                            vaultAta: vaultAta,
+                            buyerAta: buyerAta,
+                            icoTiersAccount: null,
+                            tokenProgram: TOKEN_PROGRAM_ID,
+                            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                            systemProgram: SystemProgram.programId,
+                        })
+                        .signers([buyer])
+                        .rpc({ commitment: "confirmed" })
+                );
+
+                expect.fail("Should have failed below the minimum purchase");
+            } catch (err: any) {
+                expect(getErrorString(err)).to.contain("BelowMinPurchase");
+            }
         });
 
         it("fails if hard cap is reached", async () => {
-            // We bought 5,000 already; remaining = 5,000. Trying to buy 8,000 exceeds the 10,000 hardCap.
+            // We raised 5,000,000 lamports already; 8,000 more base units would take it to 13,000,000, past the hardCap.
             const excessiveAmount = new BN(8_000);
 
             try {
                 await withRetry(() =>
-                    program.methods.purchaseToken(excessiveAmount)
+                    program.methods.purchaseToken(excessiveAmount, [], null)
                         .accountsPartial({
                             buyer: buyer.publicKey,
                             mint: mint.publicKey,
                             config: configPda,
-                            creator: creator.publicKey,
                             icoConfigAccount: icoConfigPda,
                             icoVaultAccount: icoVaultPda,
+                            icoEscrowAccount: icoEscrowPda,
+                            icoPurchaseAccount: buyerPurchasePda,
                             vaultAta: vaultAta,
                             buyerAta: buyerAta,
+                            icoTiersAccount: null,
                             tokenProgram: TOKEN_PROGRAM_ID,
                             associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
                             systemProgram: SystemProgram.programId,
@@ -326,4 +546,877 @@ describe("ico", () => {
         });
 
     });
+
+    describe("refund", () => {
+        it("fails to refund once the soft cap has been reached", async () => {
+            const waitMs = Math.max(0, (endTime + 2) * 1000 - Date.now());
+            console.log(`    Waiting ${Math.ceil(waitMs / 1000)}s for the ICO to end...`);
+            await new Promise(r => setTimeout(r, waitMs));
+
+            try {
+                await withRetry(() =>
+                    program.methods.refund()
+                        .accountsPartial({
+                            buyer: buyer.publicKey,
+                            mint: mint.publicKey,
+                            icoConfigAccount: icoConfigPda,
+                            icoVaultAccount: icoVaultPda,
+                            icoEscrowAccount: icoEscrowPda,
+                            icoPurchaseAccount: buyerPurchasePda,
+                            vaultAta: vaultAta,
+                            buyerAta: buyerAta,
+                            tokenProgram: TOKEN_PROGRAM_ID,
+                        })
+                        .signers([buyer])
+                        .rpc({ commitment: "confirmed" })
+                );
+
+                expect.fail("Should fail because the soft cap was reached");
+            } catch (err: any) {
+                expect(getErrorString(err)).to.contain("SoftCapReached");
+            }
+        });
+    });
+
+    describe("withdraw proceeds", () => {
+        it("releases escrowed SOL to the creator minus the protocol fee", async () => {
+            const configAcc = await program.account.config.fetch(configPda);
+            const escrowBalance = await connection.getBalance(icoEscrowPda);
+            const creatorBalanceBefore = await connection.getBalance(creator.publicKey);
+
+            await withRetry(() =>
+                program.methods.withdrawProceeds()
+                    .accountsPartial({
+                        creator: creator.publicKey,
+                        mint: mint.publicKey,
+                        config: configPda,
+                        protocolOwner: configAcc.owner,
+                        icoConfigAccount: icoConfigPda,
+                        icoEscrowAccount: icoEscrowPda,
+                    })
+                    .signers([creator])
+                    .rpc({ commitment: "confirmed" })
+            );
+
+            // The creator receives everything in escrow (including its rent) except the fee
+            const escrowRent = await connection.getMinimumBalanceForRentExemption(
+                program.account.icoEscrowAccount.size
+            );
+            const expectedFee = Math.floor(((escrowBalance - escrowRent) * configAcc.fee.toNumber()) / 10_000);
+            const creatorBalanceAfter = await connection.getBalance(creator.publicKey);
+            expect(creatorBalanceAfter - creatorBalanceBefore).to.equal(escrowBalance - expectedFee);
+
+            // Escrow is closed once the proceeds are released
+            const escrowInfo = await connection.getAccountInfo(icoEscrowPda);
+            expect(escrowInfo).to.be.null;
+        });
+    });
+
+    describe("reclaim unsold", () => {
+        it("returns unsold tokens to the creator and closes the vault", async () => {
+            const vaultBalance = await connection.getTokenAccountBalance(vaultAta);
+            const creatorBalanceBefore = await connection.getTokenAccountBalance(creatorAta);
+
+            await withRetry(() =>
+                program.methods.reclaimUnsold(true)
+                    .accountsPartial({
+                        creator: creator.publicKey,
+                        mint: mint.publicKey,
+                        icoConfigAccount: icoConfigPda,
+                        icoVaultAccount: icoVaultPda,
+                        vaultAta: vaultAta,
+                        creatorAta: creatorAta,
+                        tokenProgram: TOKEN_PROGRAM_ID,
+                    })
+                    .signers([creator])
+                    .rpc({ commitment: "confirmed" })
+            );
+
+            const creatorBalanceAfter = await connection.getTokenAccountBalance(creatorAta);
+            expect(
+                new BN(creatorBalanceAfter.value.amount)
+                    .sub(new BN(creatorBalanceBefore.value.amount))
+                    .eq(new BN(vaultBalance.value.amount))
+            ).to.be.true;
+
+            expect(await connection.getAccountInfo(vaultAta)).to.be.null;
+            expect(await connection.getAccountInfo(icoVaultPda)).to.be.null;
+        });
+    });
+
+    describe("vesting presale", () => {
+        const vestMint = Keypair.generate();
+        const pda = (seed: string, ...keys: PublicKey[]) =>
+            PublicKey.findProgramAddressSync(
+                [Buffer.from(seed), vestMint.publicKey.toBuffer(), ...keys.map(k => k.toBuffer())],
+                program.programId
+            )[0];
+
+        const vestIcoConfigPda = pda("ico_config_account");
+        const vestIcoVaultPda = pda("ico_vault_account");
+        const vestIcoEscrowPda = pda("ico_escrow_account");
+        const vestPurchasePda = pda("ico_purchase_account", buyer.publicKey);
+        let vestCreatorAta: PublicKey;
+        let vestVaultAta: PublicKey;
+        let vestBuyerAta: PublicKey;
+        let vestEndTime: number;
+
+        const purchased = new BN(4_000);
+        const maxPerWallet = new BN(5_000);
+
+        // Two-leaf allowlist: the buyer (with a 4,500 allocation) and some other wallet
+        const buyerAllocation = new BN(4_500);
+        const buyerLeaf = allowlistLeaf(buyer.publicKey, buyerAllocation);
+        const otherLeaf = allowlistLeaf(Keypair.generate().publicKey);
+        const merkleRoot = hashPair(buyerLeaf, otherLeaf);
+        const buyerProof = [Array.from(otherLeaf)];
+
+        before(async () => {
+            vestCreatorAta = await createSaleMint(vestMint, 10_000);
+            vestVaultAta = getAssociatedTokenAddressSync(vestMint.publicKey, vestIcoVaultPda, true, TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID);
+            vestBuyerAta = getAssociatedTokenAddressSync(vestMint.publicKey, buyer.publicKey, false, TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID);
+
+            const slot = await connection.getSlot();
+            const currentBlockTime = await connection.getBlockTime(slot) || Math.floor(Date.now() / 1000);
+            const vestStartTime = currentBlockTime + 2;
+            vestEndTime = vestStartTime + 8;
+
+            await withRetry(() =>
+                program.methods.initializeIco(
+                    new BN(1_000_000),
+                    new BN(10_000_000),
+                    new BN(vestStartTime),
+                    new BN(vestEndTime),
+                    new BN(10_000),
+                    pricePerToken,
+                    new BN(0), // no minimum purchase
+                    maxPerWallet,
+                    // 50% at TGE, the rest after a one hour cliff and one hour linear vesting
+                    { tgeUnlockBps: 5_000, cliffDuration: new BN(3_600), vestingDuration: new BN(3_600) },
+                    // The whole sale runs as an allowlisted presale
+                    { merkleRoot: Array.from(merkleRoot), endTime: new BN(vestEndTime) }
+                )
+                    .accountsPartial({
+                        creator: creator.publicKey,
+                        mint: vestMint.publicKey,
+                        config: configPda,
+                        icoConfigAccount: vestIcoConfigPda,
+                        icoVaultAccount: vestIcoVaultPda,
+                        icoEscrowAccount: vestIcoEscrowPda,
+                        vaultAta: vestVaultAta,
+                        creatorAta: vestCreatorAta,
+                        tokenProgram: TOKEN_PROGRAM_ID,
+                        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                        systemProgram: SystemProgram.programId,
+                    })
+                    .signers([creator])
+                    .rpc({ skipPreflight: true, commitment: "confirmed" })
+            );
+        });
+
+        it("rejects purchases without an allowlist proof", async () => {
+            await new Promise(r => setTimeout(r, 3000));
+
+            try {
+                await withRetry(() =>
+                    program.methods.purchaseToken(purchased, [], null)
+                        .accountsPartial({
+                            buyer: buyer.publicKey,
+                            mint: vestMint.publicKey,
+                            config: configPda,
+                            icoConfigAccount: vestIcoConfigPda,
+                            icoVaultAccount: vestIcoVaultPda,
+                            icoEscrowAccount: vestIcoEscrowPda,
+                            icoPurchaseAccount: vestPurchasePda,
+                            vaultAta: vestVaultAta,
+                            buyerAta: vestBuyerAta,
+                            icoTiersAccount: null,
+                            tokenProgram: TOKEN_PROGRAM_ID,
+                            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                            systemProgram: SystemProgram.programId,
+                        })
+                        .signers([buyer])
+                        .rpc({ commitment: "confirmed" })
+                );
+
+                expect.fail("Should have failed without a valid proof");
+            } catch (err: any) {
+                expect(getErrorString(err)).to.contain("NotAllowlisted");
+            }
+        });
+
+        it("keeps vested purchases in the vault", async () => {
+
+            await withRetry(() =>
+                program.methods.purchaseToken(purchased, buyerProof, buyerAllocation)
+                    .accountsPartial({
+                        buyer: buyer.publicKey,
+                        mint: vestMint.publicKey,
+                        config: configPda,
+                        icoConfigAccount: vestIcoConfigPda,
+                        icoVaultAccount: vestIcoVaultPda,
+                        icoEscrowAccount: vestIcoEscrowPda,
+                        icoPurchaseAccount: vestPurchasePda,
+                        vaultAta: vestVaultAta,
+                        buyerAta: vestBuyerAta,
+                        icoTiersAccount: null,
+                        tokenProgram: TOKEN_PROGRAM_ID,
+                        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                        systemProgram: SystemProgram.programId,
+                    })
+                    .signers([buyer])
+                    .rpc({ skipPreflight: true, commitment: "confirmed" })
+            );
+
+            const buyerTokenAcc = await connection.getTokenAccountBalance(vestBuyerAta);
+            expect(buyerTokenAcc.value.amount).to.equal("0");
+
+            const icoConfig = await program.account.icoConfigAccount.fetch(vestIcoConfigPda);
+            expect(icoConfig.totalUnclaimed.eq(purchased)).to.be.true;
+        });
+
+        it("fails when a wallet exceeds its maximum across purchases", async () => {
+            // 4,000 already bought; another 2,000 would take the wallet past 5,000
+            try {
+                await withRetry(() =>
+                    program.methods.purchaseToken(new BN(2_000), buyerProof, buyerAllocation)
+                        .accountsPartial({
+                            buyer: buyer.publicKey,
+                            mint: vestMint.publicKey,
+                            config: configPda,
+                            icoConfigAccount: vestIcoConfigPda,
+                            icoVaultAccount: vestIcoVaultPda,
+                            icoEscrowAccount: vestIcoEscrowPda,
+                            icoPurchaseAccount: vestPurchasePda,
+                            vaultAta: vestVaultAta,
+                            buyerAta: vestBuyerAta,
+                            icoTiersAccount: null,
+                            tokenProgram: TOKEN_PROGRAM_ID,
+                            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                            systemProgram: SystemProgram.programId,
+                        })
+                        .signers([buyer])
+                        .rpc({ commitment: "confirmed" })
+                );
+
+                expect.fail("Should have failed above the per-wallet maximum");
+            } catch (err: any) {
+                expect(getErrorString(err)).to.contain("ExceedsMaxPurchasePerWallet");
+            }
+        });
+
+        it("releases the TGE share after the sale ends", async () => {
+            const waitMs = Math.max(0, (vestEndTime + 2) * 1000 - Date.now());
+            await new Promise(r => setTimeout(r, waitMs));
+
+            const claim = () =>
+                program.methods.claimVested()
+                    .accountsPartial({
+                        buyer: buyer.publicKey,
+                        mint: vestMint.publicKey,
+                        icoConfigAccount: vestIcoConfigPda,
+                        icoVaultAccount: vestIcoVaultPda,
+                        icoPurchaseAccount: vestPurchasePda,
+                        vaultAta: vestVaultAta,
+                        buyerAta: vestBuyerAta,
+                        tokenProgram: TOKEN_PROGRAM_ID,
+                        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                        systemProgram: SystemProgram.programId,
+                    })
+                    .signers([buyer])
+                    .rpc({ commitment: "confirmed" });
+
+            await withRetry(claim);
+
+            const buyerTokenAcc = await connection.getTokenAccountBalance(vestBuyerAta);
+            expect(buyerTokenAcc.value.amount).to.equal(purchased.divn(2).toString());
+
+            // Nothing else unlocks until the cliff has passed
+            try {
+                await withRetry(claim);
+                expect.fail("Should fail because nothing is claimable during the cliff");
+            } catch (err: any) {
+                expect(getErrorString(err)).to.contain("NothingToClaim");
+            }
+        });
+    });
+
+    describe("tiered pricing", () => {
+        const tierMint = Keypair.generate();
+        const pda = (seed: string, ...keys: PublicKey[]) =>
+            PublicKey.findProgramAddressSync(
+                [Buffer.from(seed), tierMint.publicKey.toBuffer(), ...keys.map(k => k.toBuffer())],
+                program.programId
+            )[0];
+
+        const tierIcoConfigPda = pda("ico_config_account");
+        const tierIcoVaultPda = pda("ico_vault_account");
+        const tierIcoEscrowPda = pda("ico_escrow_account");
+        const tierIcoTiersPda = pda("ico_tiers_account");
+        const tierPurchasePda = pda("ico_purchase_account", buyer.publicKey);
+        let tierVaultAta: PublicKey;
+        let tierBuyerAta: PublicKey;
+
+        // Seed round: 1,000 base units at 0.5 lamports each; public round: 5,000 at 1.5 lamports each
+        const seedPrice = tokenScale.divn(2);
+        const publicPrice = tokenScale.muln(3).divn(2);
+        const seedCap = new BN(1_000);
+
+        before(async () => {
+            const tierCreatorAta = await createSaleMint(tierMint, 10_000);
+            tierVaultAta = getAssociatedTokenAddressSync(tierMint.publicKey, tierIcoVaultPda, true, TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID);
+            tierBuyerAta = getAssociatedTokenAddressSync(tierMint.publicKey, buyer.publicKey, false, TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID);
+
+            const slot = await connection.getSlot();
+            const currentBlockTime = await connection.getBlockTime(slot) || Math.floor(Date.now() / 1000);
+            const tierStartTime = currentBlockTime + 4;
+            const tierEndTime = tierStartTime + 60;
+
+            await withRetry(() =>
+                program.methods.initializeIco(
+                    new BN(0),
+                    new BN(20_000_000),
+                    new BN(tierStartTime),
+                    new BN(tierEndTime),
+                    new BN(10_000),
+                    seedPrice,
+                    new BN(0),
+                    new BN(0),
+                    null,
+                    null
+                )
+                    .accountsPartial({
+                        creator: creator.publicKey,
+                        mint: tierMint.publicKey,
+                        config: configPda,
+                        icoConfigAccount: tierIcoConfigPda,
+                        icoVaultAccount: tierIcoVaultPda,
+                        icoEscrowAccount: tierIcoEscrowPda,
+                        vaultAta: tierVaultAta,
+                        creatorAta: tierCreatorAta,
+                        tokenProgram: TOKEN_PROGRAM_ID,
+                        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                        systemProgram: SystemProgram.programId,
+                    })
+                    .signers([creator])
+                    .rpc({ skipPreflight: true, commitment: "confirmed" })
+            );
+
+            // The public round starts later, but becomes active as soon as the seed round sells out
+            await withRetry(() =>
+                program.methods.initializeTiers([
+                    { pricePerToken: seedPrice, cap: seedCap, startTime: new BN(tierStartTime), endTime: new BN(tierEndTime), sold: new BN(0) },
+                    { pricePerToken: publicPrice, cap: new BN(5_000), startTime: new BN(tierStartTime + 30), endTime: new BN(tierEndTime), sold: new BN(0) },
+                ])
+                    .accountsPartial({
+                        creator: creator.publicKey,
+                        mint: tierMint.publicKey,
+                        icoConfigAccount: tierIcoConfigPda,
+                        icoTiersAccount: tierIcoTiersPda,
+                        systemProgram: SystemProgram.programId,
+                    })
+                    .signers([creator])
+                    .rpc({ commitment: "confirmed" })
+            );
+        });
+
+        it("spills a purchase over into the next tier when the current one sells out", async () => {
+            await new Promise(r => setTimeout(r, 5000));
+
+            const amount = new BN(1_500);
+            const escrowBefore = await connection.getBalance(tierIcoEscrowPda);
+
+            await withRetry(() =>
+                program.methods.purchaseToken(amount, [], null)
+                    .accountsPartial({
+                        buyer: buyer.publicKey,
+                        mint: tierMint.publicKey,
+                        config: configPda,
+                        icoConfigAccount: tierIcoConfigPda,
+                        icoVaultAccount: tierIcoVaultPda,
+                        icoEscrowAccount: tierIcoEscrowPda,
+                        icoPurchaseAccount: tierPurchasePda,
+                        vaultAta: tierVaultAta,
+                        buyerAta: tierBuyerAta,
+                        icoTiersAccount: tierIcoTiersPda,
+                        tokenProgram: TOKEN_PROGRAM_ID,
+                        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                        systemProgram: SystemProgram.programId,
+                    })
+                    .signers([buyer])
+                    .rpc({ skipPreflight: true, commitment: "confirmed" })
+            );
+
+            // 1,000 at the seed price and the remaining 500 at the public price, rounded up to whole lamports
+            const expectedCost = seedCap.mul(seedPrice).add(amount.sub(seedCap).mul(publicPrice))
+                .add(tokenScale.subn(1)).div(tokenScale);
+            const escrowAfter = await connection.getBalance(tierIcoEscrowPda);
+            expect(escrowAfter - escrowBefore).to.equal(expectedCost.toNumber());
+
+            const tiers = await program.account.icoTiersAccount.fetch(tierIcoTiersPda);
+            expect(tiers.tiers[0].sold.eq(seedCap)).to.be.true;
+            expect(tiers.tiers[1].sold.toNumber()).to.equal(500);
+        });
+    });
+
+    describe("stablecoin payments", () => {
+        const saleMint = Keypair.generate();
+        const paymentMint = Keypair.generate();
+        const paymentDecimals = 6;
+        const pda = (seed: string, ...keys: PublicKey[]) =>
+            PublicKey.findProgramAddressSync(
+                [Buffer.from(seed), saleMint.publicKey.toBuffer(), ...keys.map(k => k.toBuffer())],
+                program.programId
+            )[0];
+
+        const saleIcoConfigPda = pda("ico_config_account");
+        const saleIcoVaultPda = pda("ico_vault_account");
+        const saleIcoEscrowPda = pda("ico_escrow_account");
+        const salePurchasePda = pda("ico_purchase_account", buyer.publicKey);
+        const paymentVault = getAssociatedTokenAddressSync(paymentMint.publicKey, saleIcoEscrowPda, true, TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID);
+        const buyerPaymentAta = getAssociatedTokenAddressSync(paymentMint.publicKey, buyer.publicKey, false, TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID);
+        let saleVaultAta: PublicKey;
+        let saleBuyerAta: PublicKey;
+
+        before(async () => {
+            const saleCreatorAta = await createSaleMint(saleMint, 10_000);
+            saleVaultAta = getAssociatedTokenAddressSync(saleMint.publicKey, saleIcoVaultPda, true, TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID);
+            saleBuyerAta = getAssociatedTokenAddressSync(saleMint.publicKey, buyer.publicKey, false, TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID);
+
+            // A 6-decimal "USDC" with 1,000 units in the buyer's wallet
+            const mintLen = getMintLen([]);
+            const mintLamports = await connection.getMinimumBalanceForRentExemption(mintLen);
+            const tx = new Transaction().add(
+                SystemProgram.createAccount({
+                    fromPubkey: payer.publicKey,
+                    newAccountPubkey: paymentMint.publicKey,
+                    space: mintLen,
+                    lamports: mintLamports,
+                    programId: TOKEN_PROGRAM_ID,
+                }),
+                createInitializeMintInstruction(paymentMint.publicKey, paymentDecimals, payer.publicKey, null, TOKEN_PROGRAM_ID),
+                createAssociatedTokenAccountInstruction(
+                    payer.publicKey, buyerPaymentAta, buyer.publicKey, paymentMint.publicKey, TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID
+                ),
+                createMintToInstruction(paymentMint.publicKey, buyerPaymentAta, payer.publicKey, 1_000 * 10 ** paymentDecimals, [], TOKEN_PROGRAM_ID)
+            );
+            await sendAndConfirmTransaction(connection, tx, [payer.payer, paymentMint], { commitment: "confirmed" });
+
+            const slot = await connection.getSlot();
+            const currentBlockTime = await connection.getBlockTime(slot) || Math.floor(Date.now() / 1000);
+            const saleStartTime = currentBlockTime + 3;
+
+            await withRetry(() =>
+                program.methods.initializeIco(
+                    new BN(0),
+                    new BN(10_000),
+                    new BN(saleStartTime),
+                    new BN(saleStartTime + 60),
+                    new BN(10_000),
+                    pricePerToken,
+                    new BN(0),
+                    new BN(0),
+                    null,
+                    null
+                )
+                    .accountsPartial({
+                        creator: creator.publicKey,
+                        mint: saleMint.publicKey,
+                        config: configPda,
+                        icoConfigAccount: saleIcoConfigPda,
+                        icoVaultAccount: saleIcoVaultPda,
+                        icoEscrowAccount: saleIcoEscrowPda,
+                        vaultAta: saleVaultAta,
+                        creatorAta: saleCreatorAta,
+                        tokenProgram: TOKEN_PROGRAM_ID,
+                        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                        systemProgram: SystemProgram.programId,
+                        paymentMint: paymentMint.publicKey,
+                        paymentVault: paymentVault,
+                        paymentTokenProgram: TOKEN_PROGRAM_ID,
+                    })
+                    .signers([creator])
+                    .rpc({ skipPreflight: true, commitment: "confirmed" })
+            );
+        });
+
+        it("takes payment in the configured mint, scaled to its decimals", async () => {
+            await new Promise(r => setTimeout(r, 4000));
+
+            const amount = new BN(1_000);
+            await withRetry(() =>
+                program.methods.purchaseToken(amount, [], null)
+                    .accountsPartial({
+                        buyer: buyer.publicKey,
+                        mint: saleMint.publicKey,
+                        config: configPda,
+                        icoConfigAccount: saleIcoConfigPda,
+                        icoVaultAccount: saleIcoVaultPda,
+                        icoEscrowAccount: saleIcoEscrowPda,
+                        icoPurchaseAccount: salePurchasePda,
+                        vaultAta: saleVaultAta,
+                        buyerAta: saleBuyerAta,
+                        icoTiersAccount: null,
+                        tokenProgram: TOKEN_PROGRAM_ID,
+                        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                        systemProgram: SystemProgram.programId,
+                        paymentMint: paymentMint.publicKey,
+                        buyerPaymentAta: buyerPaymentAta,
+                        paymentVault: paymentVault,
+                        paymentTokenProgram: TOKEN_PROGRAM_ID,
+                    })
+                    .signers([buyer])
+                    .rpc({ skipPreflight: true, commitment: "confirmed" })
+            );
+
+            // Prices are quoted with 9 decimals, so a 6-decimal mint pays 1/1,000 of the quoted cost
+            const expectedPayment = amount.mul(pricePerBaseUnit).divn(1_000);
+            const vaultBalance = await connection.getTokenAccountBalance(paymentVault);
+            expect(vaultBalance.value.amount).to.equal(expectedPayment.toString());
+
+            const purchase = await program.account.icoPurchaseAccount.fetch(salePurchasePda);
+            expect(purchase.amountPaid.eq(expectedPayment)).to.be.true;
+        });
+    });
+
+    describe("failed sale refunds", () => {
+        const failedMint = Keypair.generate();
+        const pda = (seed: string, ...keys: PublicKey[]) =>
+            PublicKey.findProgramAddressSync(
+                [Buffer.from(seed), failedMint.publicKey.toBuffer(), ...keys.map(k => k.toBuffer())],
+                program.programId
+            )[0];
+
+        const failedIcoConfigPda = pda("ico_config_account");
+        const failedIcoVaultPda = pda("ico_vault_account");
+        const failedIcoEscrowPda = pda("ico_escrow_account");
+        const failedPurchasePda = pda("ico_purchase_account", buyer.publicKey);
+        let failedVaultAta: PublicKey;
+        let failedBuyerAta: PublicKey;
+        let failedEndTime: number;
+
+        // 100 base units cost 100,000 lamports, well short of the soft cap
+        const amount = new BN(100);
+
+        before(async () => {
+            const failedCreatorAta = await createSaleMint(failedMint, 10_000);
+            failedVaultAta = getAssociatedTokenAddressSync(failedMint.publicKey, failedIcoVaultPda, true, TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID);
+            failedBuyerAta = getAssociatedTokenAddressSync(failedMint.publicKey, buyer.publicKey, false, TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID);
+
+            const slot = await connection.getSlot();
+            const currentBlockTime = await connection.getBlockTime(slot) || Math.floor(Date.now() / 1000);
+            const failedStartTime = currentBlockTime + 3;
+            failedEndTime = failedStartTime + 10;
+
+            await withRetry(() =>
+                program.methods.initializeIco(
+                    softCap,
+                    hardCap,
+                    new BN(failedStartTime),
+                    new BN(failedEndTime),
+                    new BN(10_000),
+                    pricePerToken,
+                    new BN(0),
+                    new BN(0),
+                    null,
+                    null
+                )
+                    .accountsPartial({
+                        creator: creator.publicKey,
+                        mint: failedMint.publicKey,
+                        config: configPda,
+                        icoConfigAccount: failedIcoConfigPda,
+                        icoVaultAccount: failedIcoVaultPda,
+                        icoEscrowAccount: failedIcoEscrowPda,
+                        vaultAta: failedVaultAta,
+                        creatorAta: failedCreatorAta,
+                        tokenProgram: TOKEN_PROGRAM_ID,
+                        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                        systemProgram: SystemProgram.programId,
+                    })
+                    .signers([creator])
+                    .rpc({ skipPreflight: true, commitment: "confirmed" })
+            );
+
+            await new Promise(r => setTimeout(r, 4000));
+            await withRetry(() =>
+                program.methods.purchaseToken(amount, [], null)
+                    .accountsPartial({
+                        buyer: buyer.publicKey,
+                        mint: failedMint.publicKey,
+                        config: configPda,
+                        icoConfigAccount: failedIcoConfigPda,
+                        icoVaultAccount: failedIcoVaultPda,
+                        icoEscrowAccount: failedIcoEscrowPda,
+                        icoPurchaseAccount: failedPurchasePda,
+                        vaultAta: failedVaultAta,
+                        buyerAta: failedBuyerAta,
+                        icoTiersAccount: null,
+                        tokenProgram: TOKEN_PROGRAM_ID,
+                        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                        systemProgram: SystemProgram.programId,
+                    })
+                    .signers([buyer])
+                    .rpc({ skipPreflight: true, commitment: "confirmed" })
+            );
+        });
+
+        it("returns the buyer's payment and tokens when the soft cap is missed", async () => {
+            const waitMs = Math.max(0, (failedEndTime + 2) * 1000 - Date.now());
+            console.log(`    Waiting ${Math.ceil(waitMs / 1000)}s for the ICO to end...`);
+            await new Promise(r => setTimeout(r, waitMs));
+
+            const purchase = await program.account.icoPurchaseAccount.fetch(failedPurchasePda);
+            const escrowBefore = await connection.getBalance(failedIcoEscrowPda);
+            const vaultBefore = await connection.getTokenAccountBalance(failedVaultAta);
+            const buyerTokensBefore = await connection.getTokenAccountBalance(failedBuyerAta);
+            expect(buyerTokensBefore.value.amount).to.equal(amount.toString());
+
+            await withRetry(() =>
+                program.methods.refund()
+                    .accountsPartial({
+                        buyer: buyer.publicKey,
+                        mint: failedMint.publicKey,
+                        icoConfigAccount: failedIcoConfigPda,
+                        icoVaultAccount: failedIcoVaultPda,
+                        icoEscrowAccount: failedIcoEscrowPda,
+                        icoPurchaseAccount: failedPurchasePda,
+                        vaultAta: failedVaultAta,
+                        buyerAta: failedBuyerAta,
+                        tokenProgram: TOKEN_PROGRAM_ID,
+                    })
+                    .signers([buyer])
+                    .rpc({ commitment: "confirmed" })
+            );
+
+            // The full payment leaves escrow and the tokens go back to the vault
+            const escrowAfter = await connection.getBalance(failedIcoEscrowPda);
+            expect(escrowBefore - escrowAfter).to.equal(purchase.amountPaid.toNumber());
+
+            const vaultAfter = await connection.getTokenAccountBalance(failedVaultAta);
+            expect(
+                new BN(vaultAfter.value.amount).sub(new BN(vaultBefore.value.amount)).eq(amount)
+            ).to.be.true;
+            const buyerTokensAfter = await connection.getTokenAccountBalance(failedBuyerAta);
+            expect(buyerTokensAfter.value.amount).to.equal("0");
+
+            // The purchase account is closed so the buyer cannot refund twice
+            expect(await connection.getAccountInfo(failedPurchasePda)).to.be.null;
+        });
+
+        it("closes the vault once every buyer has been refunded", async () => {
+            const failedCreatorAta = getAssociatedTokenAddressSync(failedMint.publicKey, creator.publicKey, false, TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID);
+
+            await withRetry(() =>
+                program.methods.reclaimUnsold(true)
+                    .accountsPartial({
+                        creator: creator.publicKey,
+                        mint: failedMint.publicKey,
+                        icoConfigAccount: failedIcoConfigPda,
+                        icoVaultAccount: failedIcoVaultPda,
+                        vaultAta: failedVaultAta,
+                        creatorAta: failedCreatorAta,
+                        tokenProgram: TOKEN_PROGRAM_ID,
+                    })
+                    .signers([creator])
+                    .rpc({ commitment: "confirmed" })
+            );
+
+            // Every token, including the refunded ones, is back with the creator
+            const creatorBalance = await connection.getTokenAccountBalance(failedCreatorAta);
+            expect(creatorBalance.value.amount).to.equal("10000");
+            expect(await connection.getAccountInfo(failedVaultAta)).to.be.null;
+            expect(await connection.getAccountInfo(failedIcoVaultPda)).to.be.null;
+        });
+    });
+
+    describe("migrate legacy ico config", () => {
+        // A sale in the pre-escrow layout, loaded from tests/fixtures by the local validator
+        // (see Anchor.toml): caps of 5,000 / 10,000 tokens, 2,000 sold at 3 lamports per base unit,
+        // ended without reaching its soft cap
+        const legacyCreator = Keypair.fromSeed(Buffer.alloc(32, 7));
+        const legacyMint = new PublicKey("2KW2XRd9kwqet15Aha2oK3tYvd3nWbTFH1MBiRAv1BE1");
+        const legacyPrice = 3;
+        const pda = (seed: string) =>
+            PublicKey.findProgramAddressSync([Buffer.from(seed), legacyMint.toBuffer()], program.programId)[0];
+        const legacyIcoConfigPda = pda("ico_config_account");
+        const legacyIcoEscrowPda = pda("ico_escrow_account");
+        const legacyIcoVaultPda = pda("ico_vault_account");
+
+        it("converts a legacy sale to payment units and creates its escrow", async function () {
+            const legacyInfo = await connection.getAccountInfo(legacyIcoConfigPda);
+            if (!legacyInfo) {
+                // Fixtures only exist on the local test validator
+                this.skip();
+            }
+            expect(legacyInfo.data.length).to.equal(8 + 145);
+
+            await sendAndConfirmTransaction(
+                connection,
+                new Transaction().add(
+                    SystemProgram.transfer({
+                        fromPubkey: payer.publicKey,
+                        toPubkey: legacyCreator.publicKey,
+                        lamports: 0.05 * LAMPORTS_PER_SOL,
+                    })
+                ),
+                [payer.payer],
+                { commitment: "confirmed" }
+            );
+
+            await withRetry(() =>
+                program.methods.migrateIcoConfig()
+                    .accountsPartial({
+                        creator: legacyCreator.publicKey,
+                        mint: legacyMint,
+                        icoConfigAccount: legacyIcoConfigPda,
+                        icoEscrowAccount: legacyIcoEscrowPda,
+                        systemProgram: SystemProgram.programId,
+                    })
+                    .signers([legacyCreator])
+                    .rpc({ commitment: "confirmed" })
+            );
+
+            // The account is resized to the current layout
+            const migratedInfo = await connection.getAccountInfo(legacyIcoConfigPda);
+            expect(migratedInfo.data.length).to.equal(program.account.icoConfigAccount.size);
+
+            // Token counts become lamports at the legacy price, and the price becomes fixed-point per whole token
+            const migrated = await program.account.icoConfigAccount.fetch(legacyIcoConfigPda);
+            expect(migrated.creator.toBase58()).to.equal(legacyCreator.publicKey.toBase58());
+            expect(migrated.softCap.toNumber()).to.equal(5_000 * legacyPrice);
+            expect(migrated.hardCap.toNumber()).to.equal(10_000 * legacyPrice);
+            expect(migrated.totalRaised.toNumber()).to.equal(2_000 * legacyPrice);
+            expect(migrated.tokensSold.toNumber()).to.equal(2_000);
+            expect(migrated.legacyTokensSold.toNumber()).to.equal(2_000);
+            expect(migrated.pricePerToken.eq(new BN(legacyPrice).mul(tokenScale))).to.be.true;
+            expect(migrated.totalUnclaimed.toNumber()).to.equal(0);
+            expect(migrated.paymentMint).to.be.null;
+
+            const escrow = await program.account.icoEscrowAccount.fetch(legacyIcoEscrowPda);
+            expect(escrow.mint.toBase58()).to.equal(legacyMint.toBase58());
+            expect(escrow.creator.toBase58()).to.equal(legacyCreator.publicKey.toBase58());
+        });
+
+        it("closes the vault of a failed migrated sale without waiting on legacy buyers", async function () {
+            if (!(await connection.getAccountInfo(legacyIcoVaultPda))) {
+                this.skip();
+            }
+            const legacyVaultAta = getAssociatedTokenAddressSync(legacyMint, legacyIcoVaultPda, true, TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID);
+            const legacyCreatorAta = getAssociatedTokenAddressSync(legacyMint, legacyCreator.publicKey, false, TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID);
+
+            // The 8,000 unsold tokens the legacy sale left in its vault
+            await sendAndConfirmTransaction(
+                connection,
+                new Transaction().add(
+                    createAssociatedTokenAccountInstruction(
+                        payer.publicKey, legacyVaultAta, legacyIcoVaultPda, legacyMint, TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID
+                    ),
+                    createAssociatedTokenAccountInstruction(
+                        payer.publicKey, legacyCreatorAta, legacyCreator.publicKey, legacyMint, TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID
+                    ),
+                    createMintToInstruction(legacyMint, legacyVaultAta, legacyCreator.publicKey, 8_000, [], TOKEN_PROGRAM_ID),
+                ),
+                [payer.payer, legacyCreator],
+                { commitment: "confirmed" }
+            );
+
+            // Legacy buyers have no purchase account to refund, so their tokens never leave `tokensSold`
+            const migrated = await program.account.icoConfigAccount.fetch(legacyIcoConfigPda);
+            expect(migrated.totalRaised.lt(migrated.softCap)).to.be.true;
+            expect(migrated.tokensSold.eq(migrated.legacyTokensSold)).to.be.true;
+
+            await withRetry(() =>
+                program.methods.reclaimUnsold(true)
+                    .accountsPartial({
+                        creator: legacyCreator.publicKey,
+                        mint: legacyMint,
+                        icoConfigAccount: legacyIcoConfigPda,
+                        icoVaultAccount: legacyIcoVaultPda,
+                        vaultAta: legacyVaultAta,
+                        creatorAta: legacyCreatorAta,
+                        tokenProgram: TOKEN_PROGRAM_ID,
+                    })
+                    .signers([legacyCreator])
+                    .rpc({ commitment: "confirmed" })
+            );
+
+            const creatorBalance = await connection.getTokenAccountBalance(legacyCreatorAta);
+            expect(creatorBalance.value.amount).to.equal("8000");
+            expect(await connection.getAccountInfo(legacyVaultAta)).to.be.null;
+            expect(await connection.getAccountInfo(legacyIcoVaultPda)).to.be.null;
+        });
+    });
+
+    describe("transfer hook mints", () => {
+        const hookMint = Keypair.generate();
+        // Any program id will do: the ICO rejects the transfer before Token-2022 calls the hook
+        const hookProgramId = Keypair.generate().publicKey;
+        const pda = (seed: string) =>
+            PublicKey.findProgramAddressSync([Buffer.from(seed), hookMint.publicKey.toBuffer()], program.programId)[0];
+        const hookCreatorAta = getAssociatedTokenAddressSync(hookMint.publicKey, creator.publicKey, false, TOKEN_2022_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID);
+
+        before(async () => {
+            const mintLen = getMintLen([ExtensionType.TransferHook]);
+            const mintLamports = await connection.getMinimumBalanceForRentExemption(mintLen);
+            const tx = new Transaction().add(
+                SystemProgram.createAccount({
+                    fromPubkey: payer.publicKey,
+                    newAccountPubkey: hookMint.publicKey,
+                    space: mintLen,
+                    lamports: mintLamports,
+                    programId: TOKEN_2022_PROGRAM_ID,
+                }),
+                createInitializeTransferHookInstruction(hookMint.publicKey, creator.publicKey, hookProgramId, TOKEN_2022_PROGRAM_ID),
+                createInitializeMintInstruction(hookMint.publicKey, decimals, creator.publicKey, null, TOKEN_2022_PROGRAM_ID),
+                createAssociatedTokenAccountInstruction(
+                    payer.publicKey, hookCreatorAta, creator.publicKey, hookMint.publicKey, TOKEN_2022_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID
+                ),
+                createMintToInstruction(hookMint.publicKey, hookCreatorAta, creator.publicKey, 10_000, [], TOKEN_2022_PROGRAM_ID)
+            );
+            await sendAndConfirmTransaction(connection, tx, [payer.payer, hookMint, creator], { commitment: "confirmed" });
+        });
+
+        it("rejects a sale when the hook's extra accounts are missing", async () => {
+            const slot = await connection.getSlot();
+            const currentBlockTime = await connection.getBlockTime(slot) || Math.floor(Date.now() / 1000);
+            const icoVault = pda("ico_vault_account");
+
+            try {
+                await withRetry(() =>
+                    program.methods.initializeIco(
+                        new BN(0),
+                        new BN(10_000),
+                        new BN(currentBlockTime),
+                        new BN(currentBlockTime + 60),
+                        new BN(10_000),
+                        pricePerToken,
+                        new BN(0),
+                        new BN(0),
+                        null,
+                        null
+                    )
+                        .accountsPartial({
+                            creator: creator.publicKey,
+                            mint: hookMint.publicKey,
+                            config: configPda,
+                            icoConfigAccount: pda("ico_config_account"),
+                            icoVaultAccount: icoVault,
+                            icoEscrowAccount: pda("ico_escrow_account"),
+                            vaultAta: getAssociatedTokenAddressSync(hookMint.publicKey, icoVault, true, TOKEN_2022_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID),
+                            creatorAta: hookCreatorAta,
+                            tokenProgram: TOKEN_2022_PROGRAM_ID,
+                            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                            systemProgram: SystemProgram.programId,
+                        })
+                        .signers([creator])
+                        .rpc({ commitment: "confirmed" })
+                );
+
+                expect.fail("Should fail without the hook's extra accounts");
+            } catch (err: any) {
+                expect(getErrorString(err)).to.contain("InvalidTransferHookAccounts");
+            }
+        });
+    });
 });
@@ -7,6 +7,7 @@ import {
   SystemProgram,
   LAMPORTS_PER_SOL,
   Transaction,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
   sendAndConfirmTransaction,
 } from "@solana/web3.js";
 import {
@@ -248,7 +249,9 @@ describe("transfer_hook", () => {
           null, // closeMinute
           maxTransferAmount,
           minTransferAmount,
-          nftMint.publicKey
+          nftMint.publicKey,
+          null, // nftCollection — gate on the fixed NFT mint
+          null  // tradingStartsAt — trade right away
         )
         .accountsPartial({
           payer: payer.publicKey,
@@ -281,10 +284,22 @@ describe("transfer_hook", () => {
       expect(configAccount.owner.toBase58()).to.equal(payer.publicKey.toBase58());
       expect(configAccount.mint.toBase58()).to.equal(mint.publicKey.toBase58());
       expect(configAccount.nftMintAddress.toBase58()).to.equal(nftMint.publicKey.toBase58());
+      expect(configAccount.nftCollection).to.be.null;
       expect(configAccount.whitelistEnabled).to.be.false;
       expect(configAccount.tradingTimeEnabled).to.be.false;
       expect(configAccount.maxTransferEnabled).to.be.false;
       expect(configAccount.nftGated).to.be.false;
+      expect(configAccount.blacklistEnabled).to.be.false;
+      expect(configAccount.destinationWhitelistEnabled).to.be.false;
+      expect(configAccount.volumeLimitEnabled).to.be.false;
+      expect(configAccount.cooldownEnabled).to.be.false;
+      expect(configAccount.maxWalletBalanceEnabled).to.be.false;
+      expect(configAccount.tradingDays).to.equal(0b1111111);
+      expect(configAccount.utcOffsetMinutes).to.equal(0);
+      expect(configAccount.tradingStartsAt.toNumber()).to.equal(0);
+      expect(configAccount.halted).to.be.false;
+      expect(configAccount.pendingOwner).to.be.null;
+      expect(configAccount.timelockSeconds.toNumber()).to.equal(0);
       expect(configAccount.maxTransferAmount.eq(maxTransferAmount)).to.be.true;
       expect(configAccount.minTransferAmount.eq(minTransferAmount)).to.be.true;
       expect(configAccount.openMinute).to.be.null;
@@ -310,10 +325,15 @@ describe("transfer_hook", () => {
           false, // whitelistEnabled
           false, // tradingTimeEnabled
           true,  // maxTransferEnabled
-          false  // nftGated
+          false, // nftGated
+          false, // blacklistEnabled
+          false, // destinationWhitelistEnabled
+          false, // volumeLimitEnabled
+          false, // cooldownEnabled
+          false  // maxWalletBalanceEnabled
         )
         .accountsPartial({
-          owner: payer.publicKey,
+          authority: payer.publicKey,
           config: configPda,
           mint: mint.publicKey,
         })
@@ -329,9 +349,9 @@ describe("transfer_hook", () => {
     it("fails when unauthorized user tries to update flags", async () => {
       try {
         await program.methods
-          .updateFlags(true, true, true, true)
+          .updateFlags(true, true, true, true, false, false, false, false, false)
           .accountsPartial({
-            owner: unauthorizedUser.publicKey,
+            authority: unauthorizedUser.publicKey,
             config: configPda,
             mint: mint.publicKey,
           })
@@ -348,9 +368,9 @@ describe("transfer_hook", () => {
 
     it("enables all flags", async () => {
       await program.methods
-        .updateFlags(true, false, true, false)
+        .updateFlags(true, false, true, false, false, false, false, false, false)
         .accountsPartial({
-          owner: payer.publicKey,
+          authority: payer.publicKey,
           config: configPda,
           mint: mint.publicKey,
         })
@@ -374,7 +394,7 @@ describe("transfer_hook", () => {
         .addToWhitelist()
         .accountsPartial({
           payer: payer.publicKey,
-          owner: payer.publicKey,
+          authority: payer.publicKey,
           config: configPda,
           mint: mint.publicKey,
           userPubkey: userToWhitelist.publicKey,
@@ -408,7 +428,7 @@ describe("transfer_hook", () => {
           .addToWhitelist()
           .accountsPartial({
             payer: unauthorizedUser.publicKey,
-            owner: unauthorizedUser.publicKey,
+            authority: unauthorizedUser.publicKey,
             config: configPda,
             mint: mint.publicKey,
             userPubkey: randomUser.publicKey,
@@ -429,7 +449,7 @@ describe("transfer_hook", () => {
         .removeFromWhitelist()
         .accountsPartial({
           payer: payer.publicKey,
-          owner: payer.publicKey,
+          authority: payer.publicKey,
           config: configPda,
           mint: mint.publicKey,
           userPubkey: userToWhitelist.publicKey,
@@ -457,10 +477,15 @@ describe("transfer_hook", () => {
           false, // whitelistEnabled OFF
           false, // tradingTimeEnabled OFF
           true,  // maxTransferEnabled ON
-          false  // nftGated OFF
+          false, // nftGated OFF
+          false, // blacklistEnabled OFF
+          false, // destinationWhitelistEnabled OFF
+          false, // volumeLimitEnabled OFF
+          false, // cooldownEnabled OFF
+          false  // maxWalletBalanceEnabled OFF
         )
         .accountsPartial({
-          owner: payer.publicKey,
+          authority: payer.publicKey,
           config: configPda,
           mint: mint.publicKey,
         })
@@ -569,10 +594,15 @@ describe("transfer_hook", () => {
           true,  // whitelistEnabled ON
           false, // tradingTimeEnabled OFF
           false, // maxTransferEnabled OFF
-          false  // nftGated OFF
+          false, // nftGated OFF
+          false, // blacklistEnabled OFF
+          false, // destinationWhitelistEnabled OFF
+          false, // volumeLimitEnabled OFF
+          false, // cooldownEnabled OFF
+          false  // maxWalletBalanceEnabled OFF
         )
         .accountsPartial({
-          owner: payer.publicKey,
+          authority: payer.publicKey,
           config: configPda,
           mint: mint.publicKey,
         })
@@ -625,7 +655,7 @@ describe("transfer_hook", () => {
         .addToWhitelist()
         .accountsPartial({
           payer: payer.publicKey,
-          owner: payer.publicKey,
+          authority: payer.publicKey,
           config: configPda,
           mint: mint.publicKey,
           userPubkey: payer.publicKey,
@@ -660,6 +690,90 @@ describe("transfer_hook", () => {
 
       console.log("    Transfer (whitelisted sender) tx:", sig);
     });
+
+    it("fails when the recipient is not whitelisted and both sides are checked", async () => {
+      await program.methods
+        .updateFlags(true, false, false, false, false, true, false, false, false)
+        .accountsPartial({
+          authority: payer.publicKey,
+          config: configPda,
+          mint: mint.publicKey,
+        })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      try {
+        const transferIx = await createTransferCheckedWithTransferHookInstruction(
+          connection,
+          sourceTokenAccount,
+          mint.publicKey,
+          destinationTokenAccount,
+          payer.publicKey,
+          BigInt(10_000_000_000),
+          decimals,
+          [],
+          "confirmed",
+          TOKEN_2022_PROGRAM_ID
+        );
+
+        await sendAndConfirmTransaction(
+          connection,
+          new Transaction().add(transferIx),
+          [payer.payer],
+          { commitment: "confirmed" }
+        );
+
+        expect.fail("Should have thrown an error for a recipient that is not whitelisted");
+      } catch (err: any) {
+        expect(err.toString()).to.not.contain("Should have thrown");
+        console.log("    ✓ Transfer correctly rejected (recipient not whitelisted)");
+      }
+    });
+
+    it("succeeds once the recipient is whitelisted too", async () => {
+      const [destinationWhitelistMarker] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("whitelist"),
+          mint.publicKey.toBuffer(),
+          destinationWallet.publicKey.toBuffer(),
+        ],
+        program.programId
+      );
+
+      await program.methods
+        .addToWhitelist()
+        .accountsPartial({
+          payer: payer.publicKey,
+          authority: payer.publicKey,
+          config: configPda,
+          mint: mint.publicKey,
+          userPubkey: destinationWallet.publicKey,
+          whitelistMarker: destinationWhitelistMarker,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      const transferIx = await createTransferCheckedWithTransferHookInstruction(
+        connection,
+        sourceTokenAccount,
+        mint.publicKey,
+        destinationTokenAccount,
+        payer.publicKey,
+        BigInt(10_000_000_000),
+        decimals,
+        [],
+        "confirmed",
+        TOKEN_2022_PROGRAM_ID
+      );
+
+      const sig = await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(transferIx),
+        [payer.payer],
+        { commitment: "confirmed" }
+      );
+
+      console.log("    Transfer (whitelisted sender and recipient) tx:", sig);
+    });
   });
 
   // ============================================================
@@ -670,9 +784,9 @@ describe("transfer_hook", () => {
     before(async () => {
       // Disable all flags
       await program.methods
-        .updateFlags(false, false, false, false)
+        .updateFlags(false, false, false, false, false, false, false, false, false)
         .accountsPartial({
-          owner: payer.publicKey,
+          authority: payer.publicKey,
           config: configPda,
           mint: mint.publicKey,
         })
@@ -708,148 +822,1645 @@ describe("transfer_hook", () => {
   });
 
   // ============================================================
-  // Test 7: Config account state verification
+  // Test 7: Transfer with Blacklist
   // ============================================================
 
-  describe("config state verification", () => {
-    it("correctly stores and updates open/close minutes", async () => {
-      // Create a new mint + config with trading hours set
-      const mint2 = Keypair.generate();
-      const mintLen = getMintLen([ExtensionType.TransferHook]);
-      const mintLamports = await connection.getMinimumBalanceForRentExemption(mintLen);
-
-      const createMintIx = SystemProgram.createAccount({
-        fromPubkey: payer.publicKey,
-        newAccountPubkey: mint2.publicKey,
-        space: mintLen,
-        lamports: mintLamports,
-        programId: TOKEN_2022_PROGRAM_ID,
-      });
+  describe("transfer with hook - blacklist", () => {
+    const blacklistMarkerFor = (wallet: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("blacklist"), mint.publicKey.toBuffer(), wallet.toBuffer()],
+        program.programId
+      )[0];
 
-      const initHookIx = createInitializeTransferHookInstruction(
-        mint2.publicKey,
+    const transfer = async (amount: bigint) => {
+      const transferIx = await createTransferCheckedWithTransferHookInstruction(
+        connection,
+        sourceTokenAccount,
+        mint.publicKey,
+        destinationTokenAccount,
         payer.publicKey,
-        program.programId,
-        TOKEN_2022_PROGRAM_ID
-      );
-
-      const initMintIx = createInitializeMintInstruction(
-        mint2.publicKey,
+        amount,
         decimals,
-        payer.publicKey,
-        null,
+        [],
+        "confirmed",
         TOKEN_2022_PROGRAM_ID
       );
+      return sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(transferIx),
+        [payer.payer],
+        { commitment: "confirmed" }
+      );
+    };
 
-      const tx = new Transaction().add(createMintIx, initHookIx, initMintIx);
-      await sendAndConfirmTransaction(connection, tx, [payer.payer, mint2], {
-        commitment: "confirmed",
-      });
+    before(async () => {
+      // Enable blacklist only
+      await program.methods
+        .updateFlags(false, false, false, false, true, false, false, false, false)
+        .accountsPartial({
+          authority: payer.publicKey,
+          config: configPda,
+          mint: mint.publicKey,
+        })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+    });
 
-      const [config2Pda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("config"), mint2.publicKey.toBuffer()],
-        program.programId
-      );
+    it("fails when the recipient is blacklisted", async () => {
+      await program.methods
+        .addToBlacklist()
+        .accountsPartial({
+          payer: payer.publicKey,
+          authority: payer.publicKey,
+          config: configPda,
+          mint: mint.publicKey,
+          userPubkey: destinationWallet.publicKey,
+          blacklistMarker: blacklistMarkerFor(destinationWallet.publicKey),
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
 
-      const [extraMeta2Pda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("extra-account-metas"), mint2.publicKey.toBuffer()],
-        program.programId
-      );
+      try {
+        await transfer(BigInt(10_000_000_000));
+        expect.fail("Should have thrown an error for a blacklisted recipient");
+      } catch (err: any) {
+        expect(err.toString()).to.not.contain("Should have thrown");
+        console.log("    ✓ Transfer correctly rejected (recipient blacklisted)");
+      }
+    });
 
-      // Initialize with trading hours: open at minute 540 (9 AM), close at minute 1020 (5 PM)
+    it("succeeds once the recipient is removed from the blacklist", async () => {
       await program.methods
-        .initializeRegistry(
-          540,   // openMinute = 9:00 AM UTC
-          1020,  // closeMinute = 5:00 PM UTC
-          new BN(1_000_000_000_000), // max
-          new BN(1), // min
-          nftMint.publicKey
-        )
+        .removeFromBlacklist()
         .accountsPartial({
           payer: payer.publicKey,
-          treasury: TREASURY,
-          mint: mint2.publicKey,
-          config: config2Pda,
-          extraAccountMetaList: extraMeta2Pda,
+          authority: payer.publicKey,
+          config: configPda,
+          mint: mint.publicKey,
+          userPubkey: destinationWallet.publicKey,
+          blacklistMarker: blacklistMarkerFor(destinationWallet.publicKey),
+        })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      const markerInfo = await connection.getAccountInfo(blacklistMarkerFor(destinationWallet.publicKey));
+      expect(markerInfo).to.be.null;
+
+      const sig = await transfer(BigInt(10_000_000_000));
+      console.log("    Transfer (recipient no longer blacklisted) tx:", sig);
+    });
+
+    it("fails when the sender is blacklisted", async () => {
+      await program.methods
+        .addToBlacklist()
+        .accountsPartial({
+          payer: payer.publicKey,
+          authority: payer.publicKey,
+          config: configPda,
+          mint: mint.publicKey,
+          userPubkey: payer.publicKey,
+          blacklistMarker: blacklistMarkerFor(payer.publicKey),
           systemProgram: SystemProgram.programId,
         })
         .rpc({ skipPreflight: true, commitment: "confirmed" });
 
-      const configAccount = await program.account.configAccount.fetch(config2Pda);
-      expect(configAccount.openMinute).to.equal(540);
-      expect(configAccount.closeMinute).to.equal(1020);
-      expect(configAccount.owner.toBase58()).to.equal(payer.publicKey.toBase58());
+      try {
+        await transfer(BigInt(10_000_000_000));
+        expect.fail("Should have thrown an error for a blacklisted sender");
+      } catch (err: any) {
+        expect(err.toString()).to.not.contain("Should have thrown");
+        console.log("    ✓ Transfer correctly rejected (sender blacklisted)");
+      }
     });
   });
-    describe("Test_config",async () => {
-      it("fetches and updates config account data correctly", async () => {
-        //const nftMint1 = Keypair.generate();
-      // const tx = await program.methods
-      //   .initializeRegistry(
-      //     null, // openMinute — no trading hour restriction
-      //     null, // closeMinute
-      //     maxTransferAmount,
-      //     minTransferAmount,
-      //     nftMint1.publicKey
-      //   )
-      //   .accountsPartial({
-      //     payer: payer.publicKey,
-      //     treasury: TREASURY,
-      //     mint: mint.publicKey,
-      //     config: configPda,
-      //     extraAccountMetaList: extraAccountMetaListPda,
-      //     systemProgram: SystemProgram.programId,
-      //   })
-      //   .rpc({ commitment: "confirmed" });
 
-        const configAccount = await program.account.configAccount.fetch(configPda);
-        console.log("Config Account Data:", {
-        owner: configAccount.owner.toBase58(),
-        mint: configAccount.mint.toBase58(),
-        nftMintAddress: configAccount.nftMintAddress.toBase58(),
-        whitelistEnabled: configAccount.whitelistEnabled,
-        tradingTimeEnabled: configAccount.tradingTimeEnabled,
-        maxTransferEnabled: configAccount.maxTransferEnabled,
-        nftGated: configAccount.nftGated,
-        maxTransferAmount: configAccount.maxTransferAmount.toString(),
-        minTransferAmount: configAccount.minTransferAmount.toString(),
-        openMinute: configAccount.openMinute,
-        closeMinute: configAccount.closeMinute,
-      });
+  // ============================================================
+  // Test 8: Per-wallet volume limits
+  // ============================================================
 
-      const openMin = 3;
-      const cloneMin = 5;
-      const maxTrasnfer = new BN(1000);
-      const minTransfer = new BN(1);
-      const nftMint = Keypair.generate();
+  describe("transfer with hook - per-wallet volume limit", () => {
+    const [walletStatePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("wallet-state"), mint.publicKey.toBuffer(), payer.publicKey.toBuffer()],
+      program.programId
+    );
 
-      const tx2 = await program.methods.editConfig(
-        openMin, // openMinute — no trading hour restriction
-        cloneMin, // closeMinute
-        maxTrasnfer,
-        minTransfer,
-        nftMint.publicKey
-      )
-      .accountsPartial({
-        owner: payer.publicKey,
-        config: configPda,
-        mint: mint.publicKey,
-      })
-      .rpc({ commitment: "confirmed" });
+    const transfer = async (amount: bigint) => {
+      const transferIx = await createTransferCheckedWithTransferHookInstruction(
+        connection,
+        sourceTokenAccount,
+        mint.publicKey,
+        destinationTokenAccount,
+        payer.publicKey,
+        amount,
+        decimals,
+        [],
+        "confirmed",
+        TOKEN_2022_PROGRAM_ID
+      );
+      return sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(transferIx),
+        [payer.payer],
+        { commitment: "confirmed" }
+      );
+    };
 
-      const newData = await program.account.configAccount.fetch(configPda);
-      console.log("Updated Config Account Data:", {
-        owner: newData.owner.toBase58(),
-        mint: newData.mint.toBase58(),
-        nftMintAddress: newData.nftMintAddress.toBase58(),
-        whitelistEnabled: newData.whitelistEnabled,
-        tradingTimeEnabled: newData.tradingTimeEnabled,
-        maxTransferEnabled: newData.maxTransferEnabled,
-        nftGated: newData.nftGated,
-        maxTransferAmount: newData.maxTransferAmount.toString(),
-        minTransferAmount: newData.minTransferAmount.toString(),
-        openMinute: newData.openMinute,
-        closeMinute: newData.closeMinute,
+    before(async () => {
+      // 30 tokens per hour, volume limit only
+      await program.methods
+        .editConfig(null, null, null, null, null, new BN(3_600), new BN(30_000_000_000), null, null, null, null, null, null)
+        .accountsPartial({
+          authority: payer.publicKey,
+          config: configPda,
+          mint: mint.publicKey,
+        })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      await program.methods
+        .updateFlags(false, false, false, false, false, false, true, false, false)
+        .accountsPartial({
+          authority: payer.publicKey,
+          config: configPda,
+          mint: mint.publicKey,
+        })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      await program.methods
+        .initializeWalletState()
+        .accountsPartial({
+          payer: payer.publicKey,
+          mint: mint.publicKey,
+          wallet: payer.publicKey,
+          walletState: walletStatePda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+    });
+
+    it("records volume for transfers within the window", async () => {
+      await transfer(BigInt(20_000_000_000)); // 20 tokens
+
+      const walletState = await program.account.walletState.fetch(walletStatePda);
+      expect(walletState.windowVolume.toString()).to.equal("20000000000");
+      // Windows are aligned to whole hours rather than to the first transfer
+      expect(walletState.windowStart.toNumber() % 3_600).to.equal(0);
+    });
+
+    it("fails when split transfers exceed the window volume", async () => {
+      try {
+        await transfer(BigInt(20_000_000_000)); // another 20 tokens, 40 in total
+        expect.fail("Should have thrown an error for exceeding the volume limit");
+      } catch (err: any) {
+        expect(err.toString()).to.not.contain("Should have thrown");
+        console.log("    ✓ Transfer correctly rejected (volume limit)");
+      }
+    });
+  });
+
+  // ============================================================
+  // Test 9: Transfer cooldown
+  // ============================================================
+
+  describe("transfer with hook - cooldown", () => {
+    const cooldownSeconds = 5;
+
+    const transfer = async (amount: bigint) => {
+      const transferIx = await createTransferCheckedWithTransferHookInstruction(
+        connection,
+        sourceTokenAccount,
+        mint.publicKey,
+        destinationTokenAccount,
+        payer.publicKey,
+        amount,
+        decimals,
+        [],
+        "confirmed",
+        TOKEN_2022_PROGRAM_ID
+      );
+      return sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(transferIx),
+        [payer.payer],
+        { commitment: "confirmed" }
+      );
+    };
+
+    before(async () => {
+      await program.methods
+        .editConfig(null, null, null, null, null, null, null, new BN(cooldownSeconds), null, null, null, null, null)
+        .accountsPartial({
+          authority: payer.publicKey,
+          config: configPda,
+          mint: mint.publicKey,
+        })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      // Cooldown only; the payer's wallet state was created in the volume limit tests
+      await program.methods
+        .updateFlags(false, false, false, false, false, false, false, true, false)
+        .accountsPartial({
+          authority: payer.publicKey,
+          config: configPda,
+          mint: mint.publicKey,
+        })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+    });
+
+    it("allows a transfer once the cooldown has passed", async () => {
+      await new Promise(r => setTimeout(r, (cooldownSeconds + 1) * 1000));
+      const sig = await transfer(BigInt(1_000_000_000));
+      console.log("    Transfer (after cooldown) tx:", sig);
+    });
+
+    it("fails when the next transfer arrives too soon", async () => {
+      try {
+        await transfer(BigInt(1_000_000_000));
+        expect.fail("Should have thrown an error for an active cooldown");
+      } catch (err: any) {
+        expect(err.toString()).to.not.contain("Should have thrown");
+        console.log("    ✓ Transfer correctly rejected (cooldown active)");
+      }
+    });
+  });
+
+  // ============================================================
+  // Test 10: Max wallet balance
+  // ============================================================
+
+  describe("transfer with hook - max wallet balance", () => {
+    const [balanceExemptMarker] = PublicKey.findProgramAddressSync(
+      [Buffer.from("balance-exempt"), mint.publicKey.toBuffer(), destinationWallet.publicKey.toBuffer()],
+      program.programId
+    );
+
+    const transfer = async (amount: bigint) => {
+      const transferIx = await createTransferCheckedWithTransferHookInstruction(
+        connection,
+        sourceTokenAccount,
+        mint.publicKey,
+        destinationTokenAccount,
+        payer.publicKey,
+        amount,
+        decimals,
+        [],
+        "confirmed",
+        TOKEN_2022_PROGRAM_ID
+      );
+      return sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(transferIx),
+        [payer.payer],
+        { commitment: "confirmed" }
+      );
+    };
+
+    before(async () => {
+      // The recipient may hold at most 50 tokens more than it does now
+      const destinationBalance = await connection.getTokenAccountBalance(destinationTokenAccount);
+      const maxWalletBalance = new BN(destinationBalance.value.amount).add(new BN(50_000_000_000));
+
+      await program.methods
+        .editConfig(null, null, null, null, null, null, null, null, maxWalletBalance, null, null, null, null)
+        .accountsPartial({
+          authority: payer.publicKey,
+          config: configPda,
+          mint: mint.publicKey,
+        })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      await program.methods
+        .updateFlags(false, false, false, false, false, false, false, false, true)
+        .accountsPartial({
+          authority: payer.publicKey,
+          config: configPda,
+          mint: mint.publicKey,
+        })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+    });
+
+    it("fails when the recipient would exceed the max balance", async () => {
+      try {
+        await transfer(BigInt(60_000_000_000));
+        expect.fail("Should have thrown an error for exceeding the max wallet balance");
+      } catch (err: any) {
+        expect(err.toString()).to.not.contain("Should have thrown");
+        console.log("    ✓ Transfer correctly rejected (max wallet balance)");
+      }
+    });
+
+    it("succeeds once the recipient is exempt", async () => {
+      await program.methods
+        .addBalanceExemption()
+        .accountsPartial({
+          payer: payer.publicKey,
+          owner: payer.publicKey,
+          config: configPda,
+          mint: mint.publicKey,
+          userPubkey: destinationWallet.publicKey,
+          balanceExemptMarker: balanceExemptMarker,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      const sig = await transfer(BigInt(60_000_000_000));
+      console.log("    Transfer (exempt recipient) tx:", sig);
+    });
+  });
+
+  // ============================================================
+  // Test 11: Exemptions
+  // ============================================================
+
+  describe("transfer with hook - exemptions", () => {
+    const [exemptMarker] = PublicKey.findProgramAddressSync(
+      [Buffer.from("exempt"), mint.publicKey.toBuffer(), destinationTokenAccount.toBuffer()],
+      program.programId
+    );
+    const [blacklistMarker] = PublicKey.findProgramAddressSync(
+      [Buffer.from("blacklist"), mint.publicKey.toBuffer(), payer.publicKey.toBuffer()],
+      program.programId
+    );
+
+    const transfer = async (amount: bigint) => {
+      const transferIx = await createTransferCheckedWithTransferHookInstruction(
+        connection,
+        sourceTokenAccount,
+        mint.publicKey,
+        destinationTokenAccount,
+        payer.publicKey,
+        amount,
+        decimals,
+        [],
+        "confirmed",
+        TOKEN_2022_PROGRAM_ID
+      );
+      return sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(transferIx),
+        [payer.payer],
+        { commitment: "confirmed" }
+      );
+    };
+
+    before(async () => {
+      // The payer is still on the blacklist from the blacklist tests, and the
+      // destination wallet was never whitelisted
+      await program.methods
+        .updateFlags(true, false, false, false, true, true, false, false, false)
+        .accountsPartial({
+          authority: payer.publicKey,
+          config: configPda,
+          mint: mint.publicKey,
+        })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+    });
+
+    it("still rejects a blacklisted sender when the destination pool is exempt", async () => {
+      await program.methods
+        .addExemption()
+        .accountsPartial({
+          payer: payer.publicKey,
+          owner: payer.publicKey,
+          config: configPda,
+          mint: mint.publicKey,
+          exemptPubkey: destinationTokenAccount,
+          exemptMarker: exemptMarker,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      try {
+        await transfer(BigInt(1_000_000_000));
+        expect.fail("Should have thrown an error for a blacklisted sender");
+      } catch (err: any) {
+        expect(err.toString()).to.not.contain("Should have thrown");
+        console.log("    ✓ Transfer correctly rejected (blacklisted sender, exempt pool)");
+      }
+    });
+
+    it("bypasses every other rule when the destination token account is exempt", async () => {
+      await program.methods
+        .removeFromBlacklist()
+        .accountsPartial({
+          payer: payer.publicKey,
+          authority: payer.publicKey,
+          config: configPda,
+          mint: mint.publicKey,
+          userPubkey: payer.publicKey,
+          blacklistMarker: blacklistMarker,
+        })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      // The destination wallet is not whitelisted, but the exemption skips that rule
+      const sig = await transfer(BigInt(1_000_000_000));
+      console.log("    Transfer (exempt destination) tx:", sig);
+    });
+
+    it("applies the rules again once the exemption is removed", async () => {
+      await program.methods
+        .removeExemption()
+        .accountsPartial({
+          payer: payer.publicKey,
+          owner: payer.publicKey,
+          config: configPda,
+          mint: mint.publicKey,
+          exemptPubkey: destinationTokenAccount,
+          exemptMarker: exemptMarker,
+        })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      try {
+        await transfer(BigInt(1_000_000_000));
+        expect.fail("Should have thrown an error for a recipient that is not whitelisted");
+      } catch (err: any) {
+        expect(err.toString()).to.not.contain("Should have thrown");
+        console.log("    ✓ Transfer correctly rejected (exemption removed)");
+      }
+    });
+  });
+
+  // ============================================================
+  // Test 12: Trading calendar
+  // ============================================================
+
+  describe("transfer with hook - trading calendar", () => {
+    const [calendarPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("calendar"), mint.publicKey.toBuffer()],
+      program.programId
+    );
+    let closedStart: BN;
+    let closedEnd: BN;
+
+    const transfer = async (amount: bigint) => {
+      const transferIx = await createTransferCheckedWithTransferHookInstruction(
+        connection,
+        sourceTokenAccount,
+        mint.publicKey,
+        destinationTokenAccount,
+        payer.publicKey,
+        amount,
+        decimals,
+        [],
+        "confirmed",
+        TOKEN_2022_PROGRAM_ID
+      );
+      return sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(transferIx),
+        [payer.payer],
+        { commitment: "confirmed" }
+      );
+    };
+
+    before(async () => {
+      await program.methods
+        .initializeCalendar()
+        .accountsPartial({
+          payer: payer.publicKey,
+          owner: payer.publicKey,
+          config: configPda,
+          mint: mint.publicKey,
+          tradingCalendar: calendarPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      // Trading window only, open every day at all hours
+      await program.methods
+        .updateFlags(false, true, false, false, false, false, false, false, false)
+        .accountsPartial({
+          authority: payer.publicKey,
+          config: configPda,
+          mint: mint.publicKey,
+        })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+    });
+
+    it("fails during a closed date range", async () => {
+      const slot = await connection.getSlot();
+      const now = await connection.getBlockTime(slot) || Math.floor(Date.now() / 1000);
+      closedStart = new BN(now - 3_600);
+      closedEnd = new BN(now + 3_600);
+
+      await program.methods
+        .addClosedRange(closedStart, closedEnd)
+        .accountsPartial({
+          owner: payer.publicKey,
+          config: configPda,
+          mint: mint.publicKey,
+          tradingCalendar: calendarPda,
+        })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      try {
+        await transfer(BigInt(1_000_000_000));
+        expect.fail("Should have thrown an error for a closed date");
+      } catch (err: any) {
+        expect(err.toString()).to.not.contain("Should have thrown");
+        console.log("    ✓ Transfer correctly rejected (closed date)");
+      }
+    });
+
+    it("succeeds once the closed range is removed", async () => {
+      await program.methods
+        .removeClosedRange(closedStart, closedEnd)
+        .accountsPartial({
+          owner: payer.publicKey,
+          config: configPda,
+          mint: mint.publicKey,
+          tradingCalendar: calendarPda,
+        })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      const calendar = await program.account.tradingCalendar.fetch(calendarPda);
+      expect(calendar.closedRanges).to.have.length(0);
+
+      const sig = await transfer(BigInt(1_000_000_000));
+      console.log("    Transfer (calendar open) tx:", sig);
+    });
+
+    it("fails on a weekday that is closed", async () => {
+      // Close every day
+      await program.methods
+        .editConfig(null, null, null, null, null, null, null, null, null, 0, null, null, null)
+        .accountsPartial({
+          authority: payer.publicKey,
+          config: configPda,
+          mint: mint.publicKey,
+        })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      try {
+        await transfer(BigInt(1_000_000_000));
+        expect.fail("Should have thrown an error for a closed weekday");
+      } catch (err: any) {
+        expect(err.toString()).to.not.contain("Should have thrown");
+        console.log("    ✓ Transfer correctly rejected (closed weekday)");
+      }
+
+      await program.methods
+        .editConfig(null, null, null, null, null, null, null, null, null, 0b1111111, null, null, null)
+        .accountsPartial({
+          authority: payer.publicKey,
+          config: configPda,
+          mint: mint.publicKey,
+        })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+    });
+  });
+
+  // ============================================================
+  // Test 13: Multiple trading sessions
+  // ============================================================
+
+  describe("transfer with hook - trading sessions", () => {
+    const [sessionsPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("sessions"), mint.publicKey.toBuffer()],
+      program.programId
+    );
+    let breakStart: number;
+
+    const transfer = async (amount: bigint) => {
+      const transferIx = await createTransferCheckedWithTransferHookInstruction(
+        connection,
+        sourceTokenAccount,
+        mint.publicKey,
+        destinationTokenAccount,
+        payer.publicKey,
+        amount,
+        decimals,
+        [],
+        "confirmed",
+        TOKEN_2022_PROGRAM_ID
+      );
+      return sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(transferIx),
+        [payer.payer],
+        { commitment: "confirmed" }
+      );
+    };
+
+    it("fails during the break between two sessions", async () => {
+      const slot = await connection.getSlot();
+      const now = await connection.getBlockTime(slot) || Math.floor(Date.now() / 1000);
+      const minute = Math.floor((now % 86_400) / 60);
+
+      // A morning and an afternoon session with a break around the current minute
+      breakStart = Math.max(minute - 5, 1);
+      const breakEnd = Math.min(minute + 5, 1439);
+
+      await program.methods
+        .initializeTradingSessions([
+          { openMinute: 0, closeMinute: breakStart },
+          { openMinute: breakEnd, closeMinute: 1440 },
+        ])
+        .accountsPartial({
+          payer: payer.publicKey,
+          owner: payer.publicKey,
+          config: configPda,
+          mint: mint.publicKey,
+          tradingSessions: sessionsPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      try {
+        await transfer(BigInt(1_000_000_000));
+        expect.fail("Should have thrown an error outside the trading sessions");
+      } catch (err: any) {
+        expect(err.toString()).to.not.contain("Should have thrown");
+        console.log("    ✓ Transfer correctly rejected (between sessions)");
+      }
+    });
+
+    it("succeeds when the current minute falls in a session", async () => {
+      await program.methods
+        .updateTradingSessions([
+          { openMinute: 0, closeMinute: breakStart },
+          { openMinute: breakStart, closeMinute: 1440 },
+        ])
+        .accountsPartial({
+          owner: payer.publicKey,
+          config: configPda,
+          mint: mint.publicKey,
+          tradingSessions: sessionsPda,
+        })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      const sig = await transfer(BigInt(1_000_000_000));
+      console.log("    Transfer (within a session) tx:", sig);
+    });
+
+    it("rejects overlapping sessions", async () => {
+      try {
+        await program.methods
+          .updateTradingSessions([
+            { openMinute: 0, closeMinute: 600 },
+            { openMinute: 500, closeMinute: 1440 },
+          ])
+          .accountsPartial({
+            owner: payer.publicKey,
+            config: configPda,
+            mint: mint.publicKey,
+            tradingSessions: sessionsPda,
+          })
+          .rpc({ commitment: "confirmed" });
+
+        expect.fail("Should have thrown an error for overlapping sessions");
+      } catch (err: any) {
+        expect(err.toString()).to.contain("InvalidTradingSessions");
+      }
+    });
+  });
+
+  // ============================================================
+  // Test 14: Halt and scheduled trading start
+  // ============================================================
+
+  describe("transfer with hook - halt and trading start", () => {
+    const transfer = async (amount: bigint) => {
+      const transferIx = await createTransferCheckedWithTransferHookInstruction(
+        connection,
+        sourceTokenAccount,
+        mint.publicKey,
+        destinationTokenAccount,
+        payer.publicKey,
+        amount,
+        decimals,
+        [],
+        "confirmed",
+        TOKEN_2022_PROGRAM_ID
+      );
+      return sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(transferIx),
+        [payer.payer],
+        { commitment: "confirmed" }
+      );
+    };
+
+    const accounts = () => ({
+      authority: payer.publicKey,
+      config: configPda,
+      mint: mint.publicKey,
+    });
+
+    it("freezes every transfer while halted", async () => {
+      await program.methods.halt().accountsPartial(accounts()).rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      try {
+        await transfer(BigInt(1_000_000_000));
+        expect.fail("Should have thrown an error while halted");
+      } catch (err: any) {
+        expect(err.toString()).to.not.contain("Should have thrown");
+        console.log("    ✓ Transfer correctly rejected (halted)");
+      }
+
+      await program.methods.resume().accountsPartial(accounts()).rpc({ skipPreflight: true, commitment: "confirmed" });
+      const configAccount = await program.account.configAccount.fetch(configPda);
+      expect(configAccount.halted).to.be.false;
+    });
+
+    it("does not schedule a start on a token that is already trading", async () => {
+      const slot = await connection.getSlot();
+      const now = await connection.getBlockTime(slot) || Math.floor(Date.now() / 1000);
+
+      try {
+        await program.methods
+          .editConfig(null, null, null, null, null, null, null, null, null, null, null, new BN(now + 3_600), null)
+          .accountsPartial(accounts())
+          .rpc({ commitment: "confirmed" });
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.toString()).to.contain("TradingAlreadyStarted");
+      }
+    });
+
+    describe("scheduled launch", () => {
+      const launchMint = Keypair.generate();
+      const pda = (seed: string) =>
+        PublicKey.findProgramAddressSync([Buffer.from(seed), launchMint.publicKey.toBuffer()], program.programId)[0];
+      const launchConfigPda = pda("config");
+      const launchSource = getAssociatedTokenAddressSync(launchMint.publicKey, payer.publicKey, false, TOKEN_2022_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID);
+      const launchDestination = getAssociatedTokenAddressSync(launchMint.publicKey, destinationWallet.publicKey, false, TOKEN_2022_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID);
+      const launchAccounts = { authority: payer.publicKey, config: launchConfigPda, mint: launchMint.publicKey };
+
+      const blockTime = async () => {
+        const slot = await connection.getSlot();
+        return await connection.getBlockTime(slot) || Math.floor(Date.now() / 1000);
+      };
+      const editStart = (startsAt: number) =>
+        program.methods
+          .editConfig(null, null, null, null, null, null, null, null, null, null, null, new BN(startsAt), null)
+          .accountsPartial(launchAccounts)
+          .rpc({ commitment: "confirmed" });
+      const launchTransfer = async () => {
+        const transferIx = await createTransferCheckedWithTransferHookInstruction(
+          connection,
+          launchSource,
+          launchMint.publicKey,
+          launchDestination,
+          payer.publicKey,
+          BigInt(1_000_000_000),
+          decimals,
+          [],
+          "confirmed",
+          TOKEN_2022_PROGRAM_ID
+        );
+        return sendAndConfirmTransaction(connection, new Transaction().add(transferIx), [payer.payer], { commitment: "confirmed" });
+      };
+
+      before(async () => {
+        const mintLen = getMintLen([ExtensionType.TransferHook]);
+        const mintLamports = await connection.getMinimumBalanceForRentExemption(mintLen);
+        await sendAndConfirmTransaction(
+          connection,
+          new Transaction().add(
+            SystemProgram.createAccount({
+              fromPubkey: payer.publicKey,
+              newAccountPubkey: launchMint.publicKey,
+              space: mintLen,
+              lamports: mintLamports,
+              programId: TOKEN_2022_PROGRAM_ID,
+            }),
+            createInitializeTransferHookInstruction(launchMint.publicKey, payer.publicKey, program.programId, TOKEN_2022_PROGRAM_ID),
+            createInitializeMintInstruction(launchMint.publicKey, decimals, payer.publicKey, null, TOKEN_2022_PROGRAM_ID),
+            createAssociatedTokenAccountInstruction(payer.publicKey, launchSource, payer.publicKey, launchMint.publicKey, TOKEN_2022_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID),
+            createAssociatedTokenAccountInstruction(payer.publicKey, launchDestination, destinationWallet.publicKey, launchMint.publicKey, TOKEN_2022_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID),
+            createMintToInstruction(launchMint.publicKey, launchSource, payer.publicKey, 10_000_000_000, [], TOKEN_2022_PROGRAM_ID)
+          ),
+          [payer.payer, launchMint],
+          { commitment: "confirmed" }
+        );
+
+        // Trading opens an hour from now
+        await program.methods
+          .initializeRegistry(null, null, maxTransferAmount, minTransferAmount, nftMint.publicKey, null, new BN((await blockTime()) + 3_600))
+          .accountsPartial({
+            payer: payer.publicKey,
+            treasury: TREASURY,
+            mint: launchMint.publicKey,
+            config: launchConfigPda,
+            extraAccountMetaList: pda("extra-account-metas"),
+            systemProgram: SystemProgram.programId,
+          })
+          .rpc({ skipPreflight: true, commitment: "confirmed" });
+      });
+
+      it("fails before the scheduled trading start", async () => {
+        try {
+          await launchTransfer();
+          expect.fail("Should have thrown an error before trading starts");
+        } catch (err: any) {
+          expect(err.toString()).to.not.contain("Should have thrown");
+          console.log("    ✓ Transfer correctly rejected (trading not started)");
+        }
+      });
+
+      it("rejects moving the start into the past", async () => {
+        try {
+          await editStart((await blockTime()) - 60);
+          expect.fail("Should have thrown an error");
+        } catch (err: any) {
+          expect(err.toString()).to.contain("InvalidTradingStart");
+        }
+      });
+
+      it("opens trading at the moved start", async () => {
+        await editStart((await blockTime()) + 3);
+        await new Promise(r => setTimeout(r, 5000));
+
+        const sig = await launchTransfer();
+        console.log("    Transfer (trading started) tx:", sig);
+      });
+
+      it("does not move the trading start once trading has opened", async () => {
+        try {
+          await editStart((await blockTime()) + 3_600);
+          expect.fail("Should have thrown an error");
+        } catch (err: any) {
+          expect(err.toString()).to.contain("TradingAlreadyStarted");
+        }
+      });
+    });
+  });
+
+  // ============================================================
+  // Test 15: Two-step ownership transfer
+  // ============================================================
+
+  describe("ownership transfer", () => {
+    const newOwner = Keypair.generate();
+
+    it("only lets the pending owner accept", async () => {
+      await program.methods
+        .proposeOwner(newOwner.publicKey)
+        .accountsPartial({ owner: payer.publicKey, config: configPda, mint: mint.publicKey })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      try {
+        await program.methods
+          .acceptOwner()
+          .accountsPartial({ newOwner: unauthorizedUser.publicKey, config: configPda, mint: mint.publicKey })
+          .signers([unauthorizedUser])
+          .rpc({ commitment: "confirmed" });
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.toString()).to.contain("NotPendingOwner");
+      }
+
+      await program.methods
+        .acceptOwner()
+        .accountsPartial({ newOwner: newOwner.publicKey, config: configPda, mint: mint.publicKey })
+        .signers([newOwner])
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      const configAccount = await program.account.configAccount.fetch(configPda);
+      expect(configAccount.owner.toBase58()).to.equal(newOwner.publicKey.toBase58());
+      expect(configAccount.pendingOwner).to.be.null;
+    });
+
+    it("hands ownership back to the original owner", async () => {
+      await program.methods
+        .proposeOwner(payer.publicKey)
+        .accountsPartial({ owner: newOwner.publicKey, config: configPda, mint: mint.publicKey })
+        .signers([newOwner])
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+      await program.methods
+        .acceptOwner()
+        .accountsPartial({ newOwner: payer.publicKey, config: configPda, mint: mint.publicKey })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      const configAccount = await program.account.configAccount.fetch(configPda);
+      expect(configAccount.owner.toBase58()).to.equal(payer.publicKey.toBase58());
+    });
+  });
+
+  // ============================================================
+  // Test 16: Role-based access control
+  // ============================================================
+
+  describe("roles", () => {
+    const pauser = Keypair.generate();
+    const PAUSER_ROLE = 1;
+    const [pauserRolePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("role"), mint.publicKey.toBuffer(), Buffer.from([PAUSER_ROLE]), pauser.publicKey.toBuffer()],
+      program.programId
+    );
+    const roleAccounts = {
+      payer: payer.publicKey,
+      owner: payer.publicKey,
+      config: configPda,
+      mint: mint.publicKey,
+      holder: pauser.publicKey,
+      roleMarker: pauserRolePda,
+    };
+    const pauserAccounts = {
+      authority: pauser.publicKey,
+      config: configPda,
+      mint: mint.publicKey,
+      roleMarker: pauserRolePda,
+    };
+
+    it("lets a pauser halt and resume trading", async () => {
+      await program.methods
+        .grantRole({ pauser: {} })
+        .accountsPartial(roleAccounts)
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      await program.methods.halt().accountsPartial(pauserAccounts).signers([pauser]).rpc({ skipPreflight: true, commitment: "confirmed" });
+      expect((await program.account.configAccount.fetch(configPda)).halted).to.be.true;
+
+      await program.methods.resume().accountsPartial(pauserAccounts).signers([pauser]).rpc({ skipPreflight: true, commitment: "confirmed" });
+      expect((await program.account.configAccount.fetch(configPda)).halted).to.be.false;
+    });
+
+    it("does not let a pauser update flags", async () => {
+      try {
+        await program.methods
+          .updateFlags(false, false, false, false, false, false, false, false, false)
+          .accountsPartial({ authority: pauser.publicKey, config: configPda, mint: mint.publicKey })
+          .signers([pauser])
+          .rpc({ commitment: "confirmed" });
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.toString()).to.contain("Unauthorized");
+      }
+    });
+
+    it("stops a pauser from halting once the role is revoked", async () => {
+      await program.methods
+        .revokeRole({ pauser: {} })
+        .accountsPartial(roleAccounts)
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      try {
+        await program.methods
+          .halt()
+          .accountsPartial({ authority: pauser.publicKey, config: configPda, mint: mint.publicKey })
+          .signers([pauser])
+          .rpc({ commitment: "confirmed" });
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.toString()).to.contain("Unauthorized");
+      }
+    });
+  });
+
+  // ============================================================
+  // Test 17: Timelocked config changes
+  // ============================================================
+
+  describe("config timelock", () => {
+    const [pendingChangePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("pending-config"), mint.publicKey.toBuffer()],
+      program.programId
+    );
+    const queueAccounts = {
+      authority: payer.publicKey,
+      config: configPda,
+      mint: mint.publicKey,
+      payer: payer.publicKey,
+      pendingChange: pendingChangePda,
+      systemProgram: SystemProgram.programId,
+    };
+    const pendingAccounts = {
+      payer: payer.publicKey,
+      config: configPda,
+      mint: mint.publicKey,
+      pendingChange: pendingChangePda,
+    };
+
+    before(async () => {
+      await program.methods
+        .editConfig(null, null, null, null, null, null, null, null, null, null, null, null, new BN(2))
+        .accountsPartial({ authority: payer.publicKey, config: configPda, mint: mint.publicKey })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+    });
+
+    it("queues flag updates instead of applying them", async () => {
+      const previous = await program.account.configAccount.fetch(configPda);
+
+      await program.methods
+        .updateFlags(true, true, true, true, true, true, true, true, true)
+        .accountsPartial(queueAccounts)
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      const after = await program.account.configAccount.fetch(configPda);
+      expect(after.whitelistEnabled).to.equal(previous.whitelistEnabled);
+      expect(after.blacklistEnabled).to.equal(previous.blacklistEnabled);
+
+      const pending = await program.account.pendingConfigChange.fetch(pendingChangePda);
+      expect(pending.change.flags).to.not.be.undefined;
+    });
+
+    it("refuses to renounce ownership while a change is queued", async () => {
+      try {
+        await program.methods
+          .renounceOwnership()
+          .accountsPartial({ owner: payer.publicKey, config: configPda, mint: mint.publicKey, pendingChange: pendingChangePda })
+          .rpc({ commitment: "confirmed" });
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.toString()).to.contain("ChangePending");
+      }
+    });
+
+    it("withdraws a queued change with cancel_pending", async () => {
+      await program.methods
+        .cancelPending()
+        .accountsPartial({ authority: payer.publicKey, ...pendingAccounts })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      expect(await connection.getAccountInfo(pendingChangePda)).to.be.null;
+    });
+
+    it("keeps halts immediate while timelocked", async () => {
+      const accounts = { authority: payer.publicKey, config: configPda, mint: mint.publicKey };
+      await program.methods.halt().accountsPartial(accounts).rpc({ skipPreflight: true, commitment: "confirmed" });
+      expect((await program.account.configAccount.fetch(configPda)).halted).to.be.true;
+      await program.methods.resume().accountsPartial(accounts).rpc({ skipPreflight: true, commitment: "confirmed" });
+    });
+
+    it("applies a queued change only once the delay has passed", async () => {
+      // Queue turning the timelock back off
+      await program.methods
+        .editConfig(null, null, null, null, null, null, null, null, null, null, null, null, new BN(0))
+        .accountsPartial(queueAccounts)
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      try {
+        await program.methods.applyPending().accountsPartial(pendingAccounts).rpc({ commitment: "confirmed" });
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.toString()).to.contain("TimelockNotElapsed");
+      }
+
+      await new Promise(r => setTimeout(r, 3000));
+      await program.methods.applyPending().accountsPartial(pendingAccounts).rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      const configAccount = await program.account.configAccount.fetch(configPda);
+      expect(configAccount.timelockSeconds.toNumber()).to.equal(0);
+      expect(await connection.getAccountInfo(pendingChangePda)).to.be.null;
+    });
+  });
+
+  // ============================================================
+  // Test 18: Rebuilding the meta list for a new NFT mint
+  // ============================================================
+
+  describe("update_extra_account_meta_list", () => {
+    const replacementNftMint = Keypair.generate();
+
+    const editNftMint = (nftMintAddress: PublicKey) =>
+      program.methods
+        .editConfig(null, null, null, null, nftMintAddress, null, null, null, null, null, null, null, null)
+        .accountsPartial({
+          authority: payer.publicKey,
+          config: configPda,
+          mint: mint.publicKey,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+        });
+    const rebuild = () =>
+      program.methods
+        .updateExtraAccountMetaList()
+        .accountsPartial({
+          payer: payer.publicKey,
+          config: configPda,
+          mint: mint.publicKey,
+          extraAccountMetaList: extraAccountMetaListPda,
+          systemProgram: SystemProgram.programId,
+        });
+
+    it("refuses an NFT mint change without a rebuild", async () => {
+      try {
+        await editNftMint(replacementNftMint.publicKey).rpc({ commitment: "confirmed" });
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.toString()).to.contain("MetaListRebuildRequired");
+      }
+    });
+
+    it("changes the NFT mint when the list is rebuilt in the same transaction", async () => {
+      const send = async (nftMintAddress: PublicKey) =>
+        sendAndConfirmTransaction(
+          connection,
+          new Transaction().add(
+            await editNftMint(nftMintAddress).instruction(),
+            await rebuild().instruction()
+          ),
+          [payer.payer],
+          { commitment: "confirmed" }
+        );
+
+      await send(replacementNftMint.publicKey);
+      let configAccount = await program.account.configAccount.fetch(configPda);
+      expect(configAccount.nftMintAddress.toBase58()).to.equal(replacementNftMint.publicKey.toBase58());
+
+      const metaList = await connection.getAccountInfo(extraAccountMetaListPda);
+      expect(metaList.data.includes(replacementNftMint.publicKey.toBuffer())).to.be.true;
+
+      // Restore the original NFT mint for the remaining tests
+      await send(nftMint.publicKey);
+      configAccount = await program.account.configAccount.fetch(configPda);
+      expect(configAccount.nftMintAddress.toBase58()).to.equal(nftMint.publicKey.toBase58());
+    });
+  });
+
+  // ============================================================
+  // Test 19: Collection-gated transfers
+  // ============================================================
+
+  describe("collection-gated transfers", () => {
+    // Metaplex NFTs loaded from tests/fixtures by the local validator (see Anchor.toml):
+    // one in the collection below and one whose collection is unverified
+    const nftAuthority = Keypair.fromSeed(Buffer.alloc(32, 9));
+    const collection = new PublicKey("5Z6Ay5NEcbg3xhopc522sBCRXQujkTiuDRnHGfQdcnSf");
+    const collectionNft = new PublicKey("7v54NWdBtkjuAFJrLGsS2SXnuk8nKam81mZJeeYxVFi9");
+    const unverifiedNft = new PublicKey("mBKqcnGotbsSb5vNrdyhzZ5EhqZdids9QYiTRckvi7v");
+    const metadataProgram = new PublicKey("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+    const gatedMint = Keypair.generate();
+    const passless = Keypair.generate();
+    const pda = (seed: string, ...keys: PublicKey[]) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from(seed), gatedMint.publicKey.toBuffer(), ...keys.map(k => k.toBuffer())],
+        program.programId
+      )[0];
+    const gatedConfigPda = pda("config");
+    const nftPassPda = pda("nft-pass", payer.publicKey);
+    const metadataOf = (nft: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("metadata"), metadataProgram.toBuffer(), nft.toBuffer()],
+        metadataProgram
+      )[0];
+    const ata = (tokenMint: PublicKey, owner: PublicKey, tokenProgram = TOKEN_2022_PROGRAM_ID) =>
+      getAssociatedTokenAddressSync(tokenMint, owner, false, tokenProgram, ASSOCIATED_TOKEN_PROGRAM_ID);
+
+    const transferFrom = async (sender: Keypair) => {
+      const transferIx = await createTransferCheckedWithTransferHookInstruction(
+        connection,
+        ata(gatedMint.publicKey, sender.publicKey),
+        gatedMint.publicKey,
+        ata(gatedMint.publicKey, destinationWallet.publicKey),
+        sender.publicKey,
+        BigInt(1_000_000_000),
+        decimals,
+        [],
+        "confirmed",
+        TOKEN_2022_PROGRAM_ID
+      );
+      return sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(transferIx),
+        [payer.payer, sender],
+        { commitment: "confirmed" }
+      );
+    };
+    const setNftGated = (nftGated: boolean) =>
+      program.methods
+        .updateFlags(false, false, false, nftGated, false, false, false, false, false)
+        .accountsPartial({ authority: payer.publicKey, config: gatedConfigPda, mint: gatedMint.publicKey })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+    const registerNftPass = (nft: PublicKey) =>
+      program.methods
+        .registerNftPass()
+        .accountsPartial({
+          holder: payer.publicKey,
+          mint: gatedMint.publicKey,
+          config: gatedConfigPda,
+          nftMint: nft,
+          nftMetadata: metadataOf(nft),
+          holderNftAccount: ata(nft, payer.publicKey, TOKEN_PROGRAM_ID),
+          nftPass: nftPassPda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        });
+
+    before(async function () {
+      if (!(await connection.getAccountInfo(collectionNft))) {
+        // Fixtures only exist on the local test validator
+        this.skip();
+      }
+
+      const mintLen = getMintLen([ExtensionType.TransferHook]);
+      const mintLamports = await connection.getMinimumBalanceForRentExemption(mintLen);
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(
+          SystemProgram.createAccount({
+            fromPubkey: payer.publicKey,
+            newAccountPubkey: gatedMint.publicKey,
+            space: mintLen,
+            lamports: mintLamports,
+            programId: TOKEN_2022_PROGRAM_ID,
+          }),
+          createInitializeTransferHookInstruction(gatedMint.publicKey, payer.publicKey, program.programId, TOKEN_2022_PROGRAM_ID),
+          createInitializeMintInstruction(gatedMint.publicKey, decimals, payer.publicKey, null, TOKEN_2022_PROGRAM_ID)
+        ),
+        [payer.payer, gatedMint],
+        { commitment: "confirmed" }
+      );
+
+      await program.methods
+        .initializeRegistry(null, null, maxTransferAmount, minTransferAmount, nftMint.publicKey, collection, null)
+        .accountsPartial({
+          payer: payer.publicKey,
+          treasury: TREASURY,
+          mint: gatedMint.publicKey,
+          config: gatedConfigPda,
+          extraAccountMetaList: pda("extra-account-metas"),
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      // Tokens for the pass holder and a sender without a pass, and one of each NFT for the holder
+      const tx = new Transaction();
+      for (const owner of [payer.publicKey, passless.publicKey, destinationWallet.publicKey]) {
+        tx.add(createAssociatedTokenAccountInstruction(
+          payer.publicKey, ata(gatedMint.publicKey, owner), owner, gatedMint.publicKey, TOKEN_2022_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID
+        ));
+      }
+      for (const owner of [payer.publicKey, passless.publicKey]) {
+        tx.add(createMintToInstruction(gatedMint.publicKey, ata(gatedMint.publicKey, owner), payer.publicKey, 10_000_000_000, [], TOKEN_2022_PROGRAM_ID));
+      }
+      for (const nft of [collectionNft, unverifiedNft]) {
+        tx.add(
+          createAssociatedTokenAccountInstruction(
+            payer.publicKey, ata(nft, payer.publicKey, TOKEN_PROGRAM_ID), payer.publicKey, nft, TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID
+          ),
+          createMintToInstruction(nft, ata(nft, payer.publicKey, TOKEN_PROGRAM_ID), nftAuthority.publicKey, 1, [], TOKEN_PROGRAM_ID)
+        );
+      }
+      await sendAndConfirmTransaction(connection, tx, [payer.payer, nftAuthority], { commitment: "confirmed" });
+    });
+
+    it("lets senders without a pass transfer while gating is off", async () => {
+      await transferFrom(payer.payer);
+      await transferFrom(passless);
+    });
+
+    it("refuses a pass for an NFT outside the collection", async () => {
+      try {
+        await registerNftPass(unverifiedNft).rpc({ commitment: "confirmed" });
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.toString()).to.contain("NotInCollection");
+      }
+    });
+
+    it("moves a collection NFT into the holder's pass", async () => {
+      await registerNftPass(collectionNft).rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      const pass = await connection.getTokenAccountBalance(nftPassPda);
+      expect(pass.value.amount).to.equal("1");
+    });
+
+    it("lets the pass holder transfer while gated", async () => {
+      await setNftGated(true);
+      await transferFrom(payer.payer);
+    });
+
+    it("rejects gated transfers from a sender without a pass", async () => {
+      try {
+        await transferFrom(passless);
+        expect.fail("Should have thrown an error");
+      } catch (err: any) {
+        expect(err.toString()).to.not.contain("Should have thrown");
+        console.log("    ✓ Transfer correctly rejected (no NFT pass)");
+      }
+    });
+  });
+
+  // ============================================================
+  // Test 20: Migrating a legacy config
+  // ============================================================
+
+  describe("migrate_config", () => {
+    // A mint registered before the blacklist, loaded from tests/fixtures by the local validator
+    // (see Anchor.toml): its config in the old layout, with max transfers of 1 to 1,000 tokens
+    // and trading hours 09:00-17:00 stored but off, and the old 6-entry meta list
+    const legacyOwner = Keypair.fromSeed(Buffer.alloc(32, 14));
+    const legacyMint = new PublicKey("FezWPm3UEFa4nbF76D45V3gg9eZzhSxfw3tUES1Gr3o1");
+    const legacyNftMint = new PublicKey("7EWrbxU7YpHthanStG9yF6KyHS77LBPH6f52ANJmL9rs");
+    const pda = (seed: string) =>
+      PublicKey.findProgramAddressSync([Buffer.from(seed), legacyMint.toBuffer()], program.programId)[0];
+    const legacyConfigPda = pda("config");
+    const legacyMetaListPda = pda("extra-account-metas");
+    const sourceAta = getAssociatedTokenAddressSync(legacyMint, legacyOwner.publicKey, false, TOKEN_2022_PROGRAM_ID);
+    const destinationAta = getAssociatedTokenAddressSync(legacyMint, payer.publicKey, false, TOKEN_2022_PROGRAM_ID);
+
+    const transfer = async (amount: bigint) => {
+      const transferIx = await createTransferCheckedWithTransferHookInstruction(
+        connection,
+        sourceAta,
+        legacyMint,
+        destinationAta,
+        legacyOwner.publicKey,
+        amount,
+        decimals,
+        [],
+        "confirmed",
+        TOKEN_2022_PROGRAM_ID
+      );
+      return sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(transferIx),
+        [payer.payer, legacyOwner],
+        { commitment: "confirmed" }
+      );
+    };
+    const migrateConfig = (owner: Keypair | anchor.Wallet) =>
+      program.methods
+        .migrateConfig()
+        .accountsPartial({
+          owner: owner.publicKey,
+          config: legacyConfigPda,
+          mint: legacyMint,
+          extraAccountMetaList: legacyMetaListPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers(owner instanceof Keypair ? [owner] : [])
+        .rpc({ commitment: "confirmed" });
+
+    before(async function () {
+      const legacyInfo = await connection.getAccountInfo(legacyConfigPda);
+      if (!legacyInfo) {
+        // Fixtures only exist on the local test validator
+        this.skip();
+      }
+      expect(legacyInfo.data.length).to.equal(8 + 122);
+
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(
+          SystemProgram.transfer({
+            fromPubkey: payer.publicKey,
+            toPubkey: legacyOwner.publicKey,
+            lamports: 0.1 * LAMPORTS_PER_SOL,
+          }),
+          createAssociatedTokenAccountInstruction(payer.publicKey, sourceAta, legacyOwner.publicKey, legacyMint, TOKEN_2022_PROGRAM_ID),
+          createAssociatedTokenAccountInstruction(payer.publicKey, destinationAta, payer.publicKey, legacyMint, TOKEN_2022_PROGRAM_ID),
+          createMintToInstruction(legacyMint, sourceAta, legacyOwner.publicKey, BigInt(2_000_000_000_000), [], TOKEN_2022_PROGRAM_ID)
+        ),
+        [payer.payer, legacyOwner],
+        { commitment: "confirmed" }
+      );
+    });
+
+    it("fails every transfer until the config is migrated", async () => {
+      try {
+        await transfer(BigInt(10_000_000_000));
+        expect.fail("Should have thrown an error for a legacy config");
+      } catch (err: any) {
+        expect(err.toString()).to.not.contain("Should have thrown");
+        console.log("    ✓ Transfer correctly rejected (legacy config)");
+      }
+    });
+
+    it("only lets the config owner migrate it", async () => {
+      try {
+        await migrateConfig(payer);
+        expect.fail("Should have thrown an error for a non-owner");
+      } catch (err: any) {
+        expect(err.toString()).to.contain("Unauthorized");
+      }
+    });
+
+    it("carries every legacy setting over and defaults the new ones", async () => {
+      const metaListBefore = await connection.getAccountInfo(legacyMetaListPda);
+      await migrateConfig(legacyOwner);
+
+      const configInfo = await connection.getAccountInfo(legacyConfigPda);
+      expect(configInfo.data.length).to.equal(program.account.configAccount.size);
+
+      const config = await program.account.configAccount.fetch(legacyConfigPda);
+      expect(config.owner.toBase58()).to.equal(legacyOwner.publicKey.toBase58());
+      expect(config.mint.toBase58()).to.equal(legacyMint.toBase58());
+      expect(config.nftMintAddress.toBase58()).to.equal(legacyNftMint.toBase58());
+      expect(config.whitelistEnabled).to.be.false;
+      expect(config.tradingTimeEnabled).to.be.false;
+      expect(config.maxTransferEnabled).to.be.true;
+      expect(config.nftGated).to.be.false;
+      expect(config.openMinute).to.equal(540);
+      expect(config.closeMinute).to.equal(1020);
+      expect(config.maxTransferAmount.toString()).to.equal("1000000000000");
+      expect(config.minTransferAmount.toString()).to.equal("1000000000");
+
+      expect(config.pendingOwner).to.be.null;
+      expect(config.nftCollection).to.be.null;
+      expect(config.destinationWhitelistEnabled).to.be.false;
+      expect(config.blacklistEnabled).to.be.false;
+      expect(config.volumeLimitEnabled).to.be.false;
+      expect(config.cooldownEnabled).to.be.false;
+      expect(config.maxWalletBalanceEnabled).to.be.false;
+      expect(config.volumeWindow.toNumber()).to.equal(0);
+      expect(config.maxVolumePerWindow.toNumber()).to.equal(0);
+      expect(config.cooldownSeconds.toNumber()).to.equal(0);
+      expect(config.maxWalletBalance.toNumber()).to.equal(0);
+      expect(config.tradingDays).to.equal(0b0111_1111);
+      expect(config.utcOffsetMinutes).to.equal(0);
+      expect(config.tradingStartsAt.toNumber()).to.equal(0);
+      expect(config.halted).to.be.false;
+      expect(config.timelockSeconds.toNumber()).to.equal(0);
+
+      // The meta list grows to hold the newer extra accounts
+      const metaListAfter = await connection.getAccountInfo(legacyMetaListPda);
+      expect(metaListAfter.data.length).to.be.greaterThan(metaListBefore.data.length);
+
+      try {
+        await migrateConfig(legacyOwner);
+        expect.fail("Should have thrown an error for an already migrated config");
+      } catch (err: any) {
+        expect(err.toString()).to.contain("NotLegacyConfig");
+      }
+    });
+
+    it("transfers under the migrated rules", async () => {
+      const sig = await transfer(BigInt(10_000_000_000));
+      console.log("    Transfer (migrated config) tx:", sig);
+
+      // The carried-over maximum still applies
+      try {
+        await transfer(BigInt(1_001_000_000_000));
+        expect.fail("Should have thrown an error for exceeding the max transfer");
+      } catch (err: any) {
+        expect(err.toString()).to.not.contain("Should have thrown");
+        console.log("    ✓ Transfer correctly rejected (above the migrated maximum)");
+      }
+    });
+  });
+
+  // ============================================================
+  // Test 21: Config account state verification
+  // ============================================================
+
+  describe("config state verification", () => {
+    it("correctly stores and updates open/close minutes", async () => {
+      // Create a new mint + config with trading hours set
+      const mint2 = Keypair.generate();
+      const mintLen = getMintLen([ExtensionType.TransferHook]);
+      const mintLamports = await connection.getMinimumBalanceForRentExemption(mintLen);
+
+      const createMintIx = SystemProgram.createAccount({
+        fromPubkey: payer.publicKey,
+        newAccountPubkey: mint2.publicKey,
+        space: mintLen,
+        lamports: mintLamports,
+        programId: TOKEN_2022_PROGRAM_ID,
+      });
+
+      const initHookIx = createInitializeTransferHookInstruction(
+        mint2.publicKey,
+        payer.publicKey,
+        program.programId,
+        TOKEN_2022_PROGRAM_ID
+      );
+
+      const initMintIx = createInitializeMintInstruction(
+        mint2.publicKey,
+        decimals,
+        payer.publicKey,
+        null,
+        TOKEN_2022_PROGRAM_ID
+      );
+
+      const tx = new Transaction().add(createMintIx, initHookIx, initMintIx);
+      await sendAndConfirmTransaction(connection, tx, [payer.payer, mint2], {
+        commitment: "confirmed",
+      });
+
+      const [config2Pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("config"), mint2.publicKey.toBuffer()],
+        program.programId
+      );
+
+      const [extraMeta2Pda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("extra-account-metas"), mint2.publicKey.toBuffer()],
+        program.programId
+      );
+
+      // Initialize with trading hours: open at minute 540 (9 AM), close at minute 1020 (5 PM)
+      await program.methods
+        .initializeRegistry(
+          540,   // openMinute = 9:00 AM UTC
+          1020,  // closeMinute = 5:00 PM UTC
+          new BN(1_000_000_000_000), // max
+          new BN(1), // min
+          nftMint.publicKey,
+          null, // nftCollection
+          null  // tradingStartsAt
+        )
+        .accountsPartial({
+          payer: payer.publicKey,
+          treasury: TREASURY,
+          mint: mint2.publicKey,
+          config: config2Pda,
+          extraAccountMetaList: extraMeta2Pda,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc({ skipPreflight: true, commitment: "confirmed" });
+
+      const configAccount = await program.account.configAccount.fetch(config2Pda);
+      expect(configAccount.openMinute).to.equal(540);
+      expect(configAccount.closeMinute).to.equal(1020);
+      expect(configAccount.owner.toBase58()).to.equal(payer.publicKey.toBase58());
+    });
+  });
+    describe("Test_config",async () => {
+      it("fetches and updates config account data correctly", async () => {
+        //const nftMint1 = Keypair.generate();
+      // const tx = await program.methods
+      //   .initializeRegistry(
+      //     null, // openMinute — no trading hour restriction
+      //     null, // closeMinute
+      //     maxTransferAmount,
+      //     minTransferAmount,
+      //     nftMint1.publicKey
+      //   )
+      //   .accountsPartial({
+      //     payer: payer.publicKey,
+      //     treasury: TREASURY,
+      //     mint: mint.publicKey,
+      //     config: configPda,
+      //     extraAccountMetaList: extraAccountMetaListPda,
+      //     systemProgram: SystemProgram.programId,
+      //   })
+      //   .rpc({ commitment: "confirmed" });
+
+        const configAccount = await program.account.configAccount.fetch(configPda);
+        console.log("Config Account Data:", {
+        owner: configAccount.owner.toBase58(),
+        mint: configAccount.mint.toBase58(),
+        nftMintAddress: configAccount.nftMintAddress.toBase58(),
+        whitelistEnabled: configAccount.whitelistEnabled,
+        tradingTimeEnabled: configAccount.tradingTimeEnabled,
+        maxTransferEnabled: configAccount.maxTransferEnabled,
+        nftGated: configAccount.nftGated,
+        maxTransferAmount: configAccount.maxTransferAmount.toString(),
+        minTransferAmount: configAccount.minTransferAmount.toString(),
+        openMinute: configAccount.openMinute,
+        closeMinute: configAccount.closeMinute,
+      });
+
+      const openMin = 3;
+      const cloneMin = 5;
+      const maxTrasnfer = new BN(1000);
+      const minTransfer = new BN(1);
+      const nftMint = Keypair.generate();
+
+      // A new NFT mint changes the extra accounts, so the meta list is rebuilt in the same transaction
+      const editIx = await program.methods.editConfig(
+        openMin, // openMinute — no trading hour restriction
+        cloneMin, // closeMinute
+        maxTrasnfer,
+        minTransfer,
+        nftMint.publicKey,
+        null,
+        null,
+        null,
+        null,
+        null,
+        null,
+        null,
+        null  // timelockSeconds
+      )
+      .accountsPartial({
+        authority: payer.publicKey,
+        config: configPda,
+        mint: mint.publicKey,
+        instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+      })
+      .instruction();
+      const rebuildIx = await program.methods
+        .updateExtraAccountMetaList()
+        .accountsPartial({
+          payer: payer.publicKey,
+          config: configPda,
+          mint: mint.publicKey,
+          extraAccountMetaList: extraAccountMetaListPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .instruction();
+      const tx2 = await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(editIx, rebuildIx),
+        [payer.payer],
+        { commitment: "confirmed" }
+      );
+
+      const newData = await program.account.configAccount.fetch(configPda);
+      console.log("Updated Config Account Data:", {
+        owner: newData.owner.toBase58(),
+        mint: newData.mint.toBase58(),
+        nftMintAddress: newData.nftMintAddress.toBase58(),
+        whitelistEnabled: newData.whitelistEnabled,
+        tradingTimeEnabled: newData.tradingTimeEnabled,
+        maxTransferEnabled: newData.maxTransferEnabled,
+        nftGated: newData.nftGated,
+        maxTransferAmount: newData.maxTransferAmount.toString(),
+        minTransferAmount: newData.minTransferAmount.toString(),
+        openMinute: newData.openMinute,
+        closeMinute: newData.closeMinute,
        });
       });
       it("only one update", async () => {
@@ -898,10 +2509,18 @@ describe("transfer_hook", () => {
         cloneMin, // closeMinute
         null,
         null,
-        null
+        null,
+        null,
+        null,
+        null,
+        null,
+        null,
+        null,
+        null,
+        null  // timelockSeconds
       )
       .accountsPartial({
-        owner: payer.publicKey,
+        authority: payer.publicKey,
         config: configPda,
         mint: mint.publicKey,
       })